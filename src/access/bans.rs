@@ -0,0 +1,200 @@
+use std::{
+    collections::HashMap,
+    net::{IpAddr, Ipv4Addr},
+    sync::RwLock,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::storage::PlayerStore;
+
+// Where the SRK backend keeps the ban list, other backends import it the first time they start.
+pub const BANS_FILE: &str = "bans.json";
+
+// Stored in `SrkPlayerData::ban_time` for bans that never run out.
+pub const PERMANENT_BAN: u32 = u32::MAX;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct BanList {
+    // The SRK only has room for when an account ban ends, so the reason lives here.
+    pub reasons: HashMap<u32, String>,
+    pub ranges: Vec<IpRangeBan>,
+}
+
+impl BanList {
+    pub fn is_empty(&self) -> bool {
+        self.reasons.is_empty() && self.ranges.is_empty()
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IpRangeBan {
+    pub network: Ipv4Addr,
+    pub prefix: u8,
+    pub expires: u32,
+    pub reason: String,
+}
+
+impl IpRangeBan {
+    pub fn contains(&self, address: IpAddr) -> bool {
+        let IpAddr::V4(address) = address else {
+            return false;
+        };
+
+        let mask = u32::MAX.checked_shl(32 - self.prefix as u32).unwrap_or(0);
+
+        u32::from(address) & mask == u32::from(self.network) & mask
+    }
+}
+
+// A copy of the player store's ban list, so checking a join doesn't have to ask the store. Every change is written back
+// to the store straight away.
+#[derive(Default)]
+pub struct BanManager {
+    pub list: RwLock<BanList>,
+}
+
+pub fn unix_time() -> u32 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |time| time.as_secs() as u32)
+}
+
+// 0 minutes means forever.
+pub fn ban_expiry(minutes: u32) -> u32 {
+    if minutes == 0 {
+        PERMANENT_BAN
+    } else {
+        unix_time().saturating_add(minutes.saturating_mul(60))
+    }
+}
+
+pub fn format_remaining(expires: u32) -> String {
+    if expires == PERMANENT_BAN {
+        return "permanently".to_string();
+    }
+
+    let minutes = expires.saturating_sub(unix_time()).div_ceil(60);
+
+    match (minutes / (60 * 24), minutes / 60 % 24, minutes % 60) {
+        (0, 0, m) => format!("for {m}m"),
+        (0, h, m) => format!("for {h}h {m}m"),
+        (d, h, _) => format!("for {d}d {h}h"),
+    }
+}
+
+pub fn parse_ip_range(range: &str) -> Option<(Ipv4Addr, u8)> {
+    let (address, prefix) = range.split_once('/').unwrap_or((range, "32"));
+
+    let prefix = prefix.parse::<u8>().ok().filter(|prefix| *prefix <= 32)?;
+
+    Some((address.parse().ok()?, prefix))
+}
+
+impl BanManager {
+    pub fn load(data: &dyn PlayerStore) -> Self {
+        let list = data.load_bans();
+
+        info!("bans", "Loaded {} account ban reasons and {} IP range bans.", list.reasons.len(), list.ranges.len());
+
+        Self { list: RwLock::new(list) }
+    }
+
+    fn save(&self, data: &mut dyn PlayerStore) {
+        data.save_bans(&self.list.read().unwrap());
+    }
+
+    // Returns the kick message for the ban, if this player is banned.
+    pub fn check(&self, data: &dyn PlayerStore, account_id: u32, address: IpAddr) -> Option<String> {
+        if let Some(message) = self.account_ban_message(data, account_id) {
+            return Some(message);
+        }
+
+        let now = unix_time();
+        let list = self.list.read().unwrap();
+
+        list.ranges
+            .iter()
+            .find(|ban| ban.expires > now && ban.contains(address))
+            .map(|ban| format!("Banned {}: {}", format_remaining(ban.expires), ban.reason))
+    }
+
+    pub fn account_ban_message(&self, data: &dyn PlayerStore, account_id: u32) -> Option<String> {
+        let account = data.get_account(account_id).filter(|account| account.ban_time > unix_time())?;
+
+        let list = self.list.read().unwrap();
+        let reason = list.reasons.get(&account_id).map_or("".to_string(), |reason| format!(": {reason}"));
+
+        Some(format!("Banned {}{reason}", format_remaining(account.ban_time)))
+    }
+
+    pub fn ban_account(&self, data: &mut dyn PlayerStore, account_id: u32, minutes: u32, reason: &str) -> bool {
+        let Some(mut account) = data.get_account(account_id) else {
+            return false;
+        };
+
+        account.ban_time = ban_expiry(minutes);
+        data.update_account(&account);
+
+        let mut list = self.list.write().unwrap();
+
+        if reason.is_empty() {
+            list.reasons.remove(&account_id);
+        } else {
+            list.reasons.insert(account_id, reason.to_string());
+        }
+
+        drop(list);
+        self.save(data);
+        data.save();
+
+        true
+    }
+
+    pub fn unban_account(&self, data: &mut dyn PlayerStore, account_id: u32) -> bool {
+        let Some(mut account) = data.get_account(account_id) else {
+            return false;
+        };
+
+        account.ban_time = 0;
+        data.update_account(&account);
+
+        self.list.write().unwrap().reasons.remove(&account_id);
+        self.save(data);
+        data.save();
+
+        true
+    }
+
+    pub fn ban_range(&self, data: &mut dyn PlayerStore, network: Ipv4Addr, prefix: u8, minutes: u32, reason: &str) {
+        let mut list = self.list.write().unwrap();
+
+        list.ranges.retain(|ban| ban.network != network || ban.prefix != prefix);
+        list.ranges.push(IpRangeBan {
+            network,
+            prefix,
+            expires: ban_expiry(minutes),
+            reason: reason.to_string(),
+        });
+
+        drop(list);
+        self.save(data);
+        data.save();
+    }
+
+    pub fn unban_range(&self, data: &mut dyn PlayerStore, network: Ipv4Addr, prefix: u8) -> bool {
+        let mut list = self.list.write().unwrap();
+
+        let count = list.ranges.len();
+        list.ranges.retain(|ban| ban.network != network || ban.prefix != prefix);
+        let removed = list.ranges.len() != count;
+
+        drop(list);
+
+        if removed {
+            self.save(data);
+            data.save();
+        }
+
+        removed
+    }
+}
@@ -0,0 +1,3 @@
+pub mod bans;
+pub mod permissions;
+pub mod whitelist;
@@ -0,0 +1,101 @@
+use std::{
+    fmt::Display,
+    str::FromStr,
+    time::{Duration, Instant},
+};
+
+use dashmap::DashMap;
+use serde::{Deserialize, Serialize};
+
+use crate::{config::config_main::ConfigMain, storage::PlayerStore};
+
+// What config.txt used to ship with, so it's as good as no password.
+pub const OLD_DEFAULT_ADMIN_PASSWORD: &str = "admin";
+
+// Wrong passwords an account gets before /login stops listening to it for a while.
+pub const MAX_LOGIN_ATTEMPTS: u32 = 3;
+pub const LOGIN_LOCKOUT: Duration = Duration::from_secs(5 * 60);
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Role {
+    #[default]
+    Player,
+    Moderator,
+    Admin,
+}
+
+impl Display for Role {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Role::Player => write!(f, "player"),
+            Role::Moderator => write!(f, "moderator"),
+            Role::Admin => write!(f, "admin"),
+        }
+    }
+}
+
+impl FromStr for Role {
+    type Err = ();
+
+    fn from_str(role: &str) -> Result<Self, Self::Err> {
+        match role.trim().to_lowercase().as_str() {
+            "player" => Ok(Role::Player),
+            "moderator" => Ok(Role::Moderator),
+            "admin" => Ok(Role::Admin),
+            _ => Err(()),
+        }
+    }
+}
+
+// The role an account gets on join, the higher of what the config and the player store give it. `/login` can still
+// bump it up to admin for the session.
+pub fn role_for_account(config: &ConfigMain, data: &dyn PlayerStore, account_id: u32) -> Role {
+    let configured = if config.admins.contains(&account_id) {
+        Role::Admin
+    } else if config.moderators.contains(&account_id) {
+        Role::Moderator
+    } else {
+        Role::Player
+    };
+
+    configured.max(data.get_account(account_id).map_or(Role::Player, |account| account.role))
+}
+
+// /login is off until the admin password is set to something of your own.
+pub fn login_enabled(config: &ConfigMain) -> bool {
+    !config.admin_password.is_empty() && config.admin_password != OLD_DEFAULT_ADMIN_PASSWORD
+}
+
+// Failed logins by account id, so reconnecting doesn't give them more tries. Not saved, a restart clears them.
+#[derive(Default)]
+pub struct LoginAttempts {
+    failures: DashMap<u32, (u32, Instant)>,
+}
+
+impl LoginAttempts {
+    // How much longer they have to wait, if they've run out of tries.
+    pub fn locked_for(&self, account_id: u32) -> Option<Duration> {
+        let (failures, last) = *self.failures.get(&account_id)?;
+
+        (failures >= MAX_LOGIN_ATTEMPTS).then(|| LOGIN_LOCKOUT.checked_sub(last.elapsed())).flatten()
+    }
+
+    // Returns how many tries they have left. The count starts over once a lockout has run out.
+    pub fn fail(&self, account_id: u32) -> u32 {
+        let mut entry = self.failures.entry(account_id).or_insert((0, Instant::now()));
+
+        if entry.1.elapsed() >= LOGIN_LOCKOUT {
+            entry.0 = 0;
+        }
+
+        entry.0 += 1;
+        entry.1 = Instant::now();
+
+        MAX_LOGIN_ATTEMPTS.saturating_sub(entry.0)
+    }
+
+    pub fn succeed(&self, account_id: u32) {
+        self.failures.remove(&account_id);
+    }
+}
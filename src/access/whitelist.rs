@@ -0,0 +1,112 @@
+use std::{
+    collections::BTreeSet,
+    fs::File,
+    path::Path,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        RwLock,
+    },
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::packets::masterserver::auth::MasterServerAuthPacket;
+
+pub const WHITELIST_FILE: &str = "whitelist.json";
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Whitelist {
+    pub account_ids: BTreeSet<u32>,
+    pub steam_ids: BTreeSet<u64>,
+}
+
+#[derive(Default)]
+pub struct WhitelistManager {
+    pub enabled: AtomicBool,
+    pub list: RwLock<Whitelist>,
+}
+
+impl WhitelistManager {
+    pub fn read_from_file(enabled: bool) -> Self {
+        let list = if Path::new(WHITELIST_FILE).exists() {
+            let file = File::open(WHITELIST_FILE).expect("Failed to open whitelist.json");
+
+            serde_json::from_reader(file).expect("Failed to parse whitelist.json")
+        } else {
+            Whitelist::default()
+        };
+
+        info!(
+            "whitelist",
+            "Loaded {} account ids and {} steam ids, whitelist is {}.",
+            list.account_ids.len(),
+            list.steam_ids.len(),
+            if enabled { "on" } else { "off" }
+        );
+
+        Self {
+            enabled: AtomicBool::new(enabled),
+            list: RwLock::new(list),
+        }
+    }
+
+    pub fn save(&self) {
+        let list = self.list.read().unwrap();
+
+        match File::create(WHITELIST_FILE) {
+            Ok(file) => {
+                if serde_json::to_writer_pretty(file, &*list).is_err() {
+                    error!("whitelist", "Failed to save whitelist!");
+                }
+            }
+            Err(_) => error!("whitelist", "Failed to open whitelist file!"),
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled.load(Ordering::Relaxed)
+    }
+
+    pub fn set_enabled(&self, enabled: bool) {
+        self.enabled.store(enabled, Ordering::Relaxed);
+    }
+
+    // Everyone gets in while the whitelist is off.
+    pub fn allows(&self, auth: &MasterServerAuthPacket) -> bool {
+        if !self.is_enabled() {
+            return true;
+        }
+
+        let list = self.list.read().unwrap();
+
+        list.account_ids.contains(&auth.account_id) || list.steam_ids.contains(&auth.steam_id)
+    }
+
+    pub fn add_account(&self, account_id: u32) -> bool {
+        let added = self.list.write().unwrap().account_ids.insert(account_id);
+        self.save();
+
+        added
+    }
+
+    pub fn remove_account(&self, account_id: u32) -> bool {
+        let removed = self.list.write().unwrap().account_ids.remove(&account_id);
+        self.save();
+
+        removed
+    }
+
+    pub fn add_steam_id(&self, steam_id: u64) -> bool {
+        let added = self.list.write().unwrap().steam_ids.insert(steam_id);
+        self.save();
+
+        added
+    }
+
+    pub fn remove_steam_id(&self, steam_id: u64) -> bool {
+        let removed = self.list.write().unwrap().steam_ids.remove(&steam_id);
+        self.save();
+
+        removed
+    }
+}
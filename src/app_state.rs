@@ -1,20 +1,28 @@
 use std::{
     net::SocketAddr,
-    sync::{Arc, Mutex, RwLock},
+    sync::{atomic::{AtomicBool, Ordering}, Arc, Mutex, RwLock, RwLockReadGuard},
 };
 
 use dashmap::DashMap;
 
 use crate::{
-    config::config_main::ConfigMain, connection::{events::{
-        event_types::{chat::EventChat, Event}, EventManager
-    }, ClientConnection}, items::ItemManager, masterserver::MasterServer, packets::{masterserver::auth::MasterServerAuthPacket, GameState}, scheduler::TaskScheduler, srk_parser::SrkData, vehicles::VehicleManager, voice::VoiceManager
+    access::{bans::BanManager, permissions::{role_for_account, LoginAttempts, Role}, whitelist::WhitelistManager}, chat::ChatManager, config::config_main::ConfigMain, corporations::CorporationManager, connection::{events::{
+        event_types::{chat::{EventChat, MAX_CHAT_LENGTH}, team_door_state::EventTeamDoorState, Event}, EventManager
+    }, ClientConnection}, items::ItemManager, logging, masterserver::MasterServer, metrics::Metrics, packets::{clientbound::kick::ClientboundKickPacket, masterserver::auth::MasterServerAuthPacket, utils::{split_text, to_ascii}, Encodable, GameState, Team}, scheduler::TaskScheduler, stocks::StockManager, storage::PlayerStore, vehicles::VehicleManager, voice::VoiceManager
 };
 
+// How many bad packets in a row a client can send before they get kicked.
+pub const MAX_PROTOCOL_ERRORS: u32 = 16;
+
+// The game has 64 player slots, but the lobby ready list only has room for 32.
+pub const MAX_CLIENTS: u32 = 32;
+
 #[derive(Default)]
 pub struct GameManager {
     pub ready: Mutex<[bool; 32]>,
     pub state: RwLock<GameState>,
+    // Only the ones that have been changed, the rest are however the map has them.
+    pub team_doors: DashMap<Team, bool>,
 }
 
 #[derive(Clone, Copy, Debug)]
@@ -32,12 +40,20 @@ pub struct AppState {
     pub round_number: RwLock<u32>,
     pub map_name: RwLock<String>,
     pub masterserver: MasterServer,
-    pub srk_data: Arc<Mutex<SrkData>>,
-    pub config: ConfigMain,
+    pub player_store: Mutex<Box<dyn PlayerStore + Send>>,
+    pub bans: BanManager,
+    pub whitelist: WhitelistManager,
+    pub logins: LoginAttempts,
+    pub config: RwLock<ConfigMain>,
+    pub running: AtomicBool,
+    pub metrics: Arc<Metrics>,
     pub events: EventManager,
+    pub chat: ChatManager,
     pub voices: VoiceManager,
     pub items: ItemManager,
     pub vehicles: VehicleManager,
+    pub stocks: StockManager,
+    pub corporations: CorporationManager,
     pub tasks: TaskScheduler,
     pub connections: DashMap<SocketAddr, ClientConnection>,
     pub auth_data: DashMap<u32, (i32, MasterServerAuthPacket)>,
@@ -53,14 +69,27 @@ impl AppState {
         writer.push(data);
     }
 
-    pub fn find_empty_slot_id(&self) -> u32 {
-        for i in 0..64 {
-            if !self.events.players.contains_key(&i) {
-                return i;
-            }
+    pub fn find_empty_slot_id(&self) -> Option<u32> {
+        (0..MAX_CLIENTS).find(|i| !self.events.players.contains_key(i))
+    }
+
+    pub fn has_reserved_slot(&self, account_id: u32) -> bool {
+        let config = self.config();
+
+        config.reserved_accounts.contains(&account_id) || role_for_account(&config, self.player_store.lock().unwrap().as_ref(), account_id) > Role::Player
+    }
+
+    // Picks a client id for someone joining, or the kick reason if there's no room for them.
+    pub fn find_join_slot(&self, account_id: u32) -> Result<u32, String> {
+        let players = self.connections.len() as u32;
+        let max_players = (self.config().max_players as u32).min(MAX_CLIENTS);
+        let public_slots = max_players.saturating_sub(self.config().reserved_slots as u32);
+
+        if players >= max_players || (players >= public_slots && !self.has_reserved_slot(account_id)) {
+            return Err(format!("The server is full ({players}/{max_players})."));
         }
 
-        0
+        self.find_empty_slot_id().ok_or("The server has run out of player slots.".to_string())
     }
 
     pub fn reparent_connection(&self, src: SocketAddr, dst: SocketAddr) {
@@ -86,6 +115,60 @@ impl AppState {
         None
     }
 
+    // Matches a client id, then a full name, then the start of a name.
+    pub fn find_connection(&self, query: &str) -> Option<SocketAddr> {
+        let query = query.to_lowercase();
+
+        if let Ok(client_id) = query.parse::<u32>()
+            && let Some(conn) = self.connections.iter().find(|conn| conn.client_id == client_id)
+        {
+            return Some(conn.address);
+        }
+
+        if let Some(conn) = self.connections.iter().find(|conn| conn.username.to_lowercase() == query) {
+            return Some(conn.address);
+        }
+
+        let matches = self.connections.iter().filter(|conn| conn.username.to_lowercase().starts_with(&query)).map(|conn| conn.address).collect::<Vec<SocketAddr>>();
+
+        if matches.len() == 1 { matches.first().copied() } else { None }
+    }
+
+    pub fn kick(&self, client_id: u32, reason: &str) -> bool {
+        let Some(address) = self.connections.iter().find(|conn| conn.client_id == client_id).map(|conn| conn.address) else {
+            return false;
+        };
+
+        let Some((_, mut connection)) = self.connections.remove(&address) else {
+            return false;
+        };
+
+        // Skip the send queue, handle_leave stops it before it would get flushed.
+        connection.send_data_immediately(ClientboundKickPacket { reason: reason.to_string() }.encode(self));
+        connection.handle_leave(self);
+
+        info!("server", "{} was kicked: {reason}", connection.username);
+
+        true
+    }
+
+    pub fn protocol_error(&self, address: SocketAddr) {
+        let client_id = match self.connections.get_mut(&address) {
+            Some(mut connection) => {
+                connection.protocol_errors += 1;
+
+                if connection.protocol_errors < MAX_PROTOCOL_ERRORS {
+                    return;
+                }
+
+                connection.client_id
+            }
+            None => return,
+        };
+
+        self.kick(client_id, "Protocol error.");
+    }
+
     pub fn do_broadcast(&self) {
         let connections = self.connections.iter();
         let broadcast = self.for_broadcast.read().unwrap().clone();
@@ -100,10 +183,123 @@ impl AppState {
         writer.clear();
     }
 
+    pub fn config(&self) -> RwLockReadGuard<'_, ConfigMain> {
+        self.config.read().unwrap()
+    }
+
+    // Rereads config.txt. Returns the keys that changed but only take effect after a restart, those keep their old values.
+    pub fn reload_config(&self) -> Vec<&'static str> {
+        let mut new = ConfigMain::read_from_file();
+        let mut config = self.config.write().unwrap();
+        let mut needs_restart = vec![];
+
+        if new.port != config.port {
+            new.port = config.port;
+            needs_restart.push("port");
+        }
+
+        if new.gamemode != config.gamemode {
+            new.gamemode = config.gamemode;
+            needs_restart.push("gamemode");
+        }
+
+        if new.master_server_url != config.master_server_url {
+            new.master_server_url.clone_from(&config.master_server_url);
+            needs_restart.push("master_server_url");
+        }
+
+        if new.player_store != config.player_store || new.sqlite_path != config.sqlite_path {
+            new.player_store = config.player_store;
+            new.sqlite_path.clone_from(&config.sqlite_path);
+            needs_restart.push("player_store");
+        }
+
+        if new.rcon_address != config.rcon_address || new.rcon_port != config.rcon_port || new.rcon_password != config.rcon_password {
+            new.rcon_address.clone_from(&config.rcon_address);
+            new.rcon_port = config.rcon_port;
+            new.rcon_password.clone_from(&config.rcon_password);
+            needs_restart.push("rcon");
+        }
+
+        if new.metrics_address != config.metrics_address || new.metrics_port != config.metrics_port {
+            new.metrics_address.clone_from(&config.metrics_address);
+            new.metrics_port = config.metrics_port;
+            needs_restart.push("metrics");
+        }
+
+        self.whitelist.set_enabled(new.whitelist);
+        logging::configure(&new);
+
+        // Promote anyone the new lists name now, demotions wait until they rejoin.
+        {
+            let data = self.player_store.lock().unwrap();
+
+            for mut connection in self.connections.iter_mut() {
+                connection.role = connection.role.max(role_for_account(&new, data.as_ref(), connection.account_id));
+            }
+        }
+
+        let uniforms_changed = new.team_uniforms != config.team_uniforms;
+
+        *config = new;
+        drop(config);
+
+        if uniforms_changed {
+            for connection in self.connections.iter() {
+                connection.update_player(self);
+            }
+        }
+
+        needs_restart
+    }
+
+    pub fn running(&self) -> bool {
+        self.running.load(Ordering::Relaxed)
+    }
+
+    pub fn shutdown(&self) {
+        self.running.store(false, Ordering::Relaxed);
+    }
+
+    // Writes everyones money, ratings and play time back to the player store, along with the corporations.
+    pub fn save_players(&self) {
+        let mut data = self.player_store.lock().unwrap();
+
+        data.begin_batch();
+
+        for mut connection in self.connections.iter_mut() {
+            connection.store_account(data.as_mut(), self.network_tick(), self.config().gamemode);
+        }
+
+        self.corporations.save(data.as_mut());
+
+        data.end_batch();
+        data.save();
+    }
+
     pub fn game_state(&self) -> GameState {
         *self.game_state.state.read().unwrap()
     }
 
+    pub fn set_game_state(&self, new: GameState) {
+        let old = std::mem::replace(&mut *self.game_state.state.write().unwrap(), new);
+
+        // Shares move on what each corporation's players earned between a round starting and finishing.
+        if old != GameState::InGame && new == GameState::InGame {
+            self.stocks.start_round(&self.net_worths());
+        } else if old == GameState::InGame && new != GameState::InGame {
+            self.stocks.end_round(&self.net_worths());
+        }
+    }
+
+    // (account id, team, money plus shares at today's prices) for everyone connected.
+    fn net_worths(&self) -> Vec<(u32, Team, i64)> {
+        self.connections
+            .iter()
+            .map(|conn| (conn.account_id, conn.team, conn.money as i64 + self.stocks.portfolio_value(&conn.stocks) as i64))
+            .collect()
+    }
+
     pub fn map_name(&self) -> String {
         self.map_name.read().unwrap().clone()
     }
@@ -116,15 +312,76 @@ impl AppState {
         *self.round_number.read().unwrap()
     }
 
-    pub fn send_chat(&self, chat_type: ChatType, message: &str, speaker_id: i32, volume: i32) {
-        let event = Event::Chat(EventChat {
+    // The current state of every player, vehicle and team door, for clients that just joined or missed events.
+    // Round number and game state don't need it, they're in the initial sync and every game packet.
+    pub fn snapshot_events(&self) -> Vec<Event> {
+        let tick_created = self.network_tick();
+        let mut events = vec![];
+
+        for connection in self.connections.iter() {
+            events.push(connection.player_event(self));
+            events.push(connection.round_event(self));
+        }
+
+        for vehicle in self.vehicles.vehicles.iter() {
+            events.push(vehicle.type_color_event(tick_created));
+        }
+
+        for door in self.game_state.team_doors.iter() {
+            events.push(Event::TeamDoorState(EventTeamDoorState {
+                tick_created,
+                team_id: *door.key() as i32,
+                door_open: *door.value(),
+            }));
+        }
+
+        events
+    }
+
+    pub fn send_snapshot(&self, client_ids: &[u32]) {
+        for event in self.snapshot_events() {
+            self.events.emit_to(client_ids, event);
+        }
+    }
+
+    pub fn set_team_door(&self, team: Team, open: bool) {
+        self.game_state.team_doors.insert(team, open);
+
+        self.events.emit_globally(Event::TeamDoorState(EventTeamDoorState {
             tick_created: self.network_tick(),
-            message: message.to_string(),
-            chat_type,
-            speaker_id,
-            volume,
-        });
+            team_id: team as i32,
+            door_open: open,
+        }));
+    }
+
+    // Anything too long for one chat event is sent as several.
+    fn chat_events(&self, chat_type: ChatType, message: &str, speaker_id: i32, volume: i32) -> Vec<Event> {
+        split_text(&to_ascii(message), MAX_CHAT_LENGTH)
+            .into_iter()
+            .map(|message| {
+                Event::Chat(EventChat {
+                    tick_created: self.network_tick(),
+                    message,
+                    chat_type,
+                    speaker_id,
+                    volume,
+                })
+            })
+            .collect()
+    }
 
-        self.events.emit_globally(event);
+    pub fn send_chat(&self, chat_type: ChatType, message: &str, speaker_id: i32, volume: i32) {
+        // Private messages use the speaker id for who they're to, nobody else should get them.
+        if matches!(chat_type, ChatType::PrivateMessage) {
+            self.send_chat_to(&[speaker_id as u32], chat_type, message, speaker_id, volume);
+        } else {
+            self.events.emit_globally_mult(self.chat_events(chat_type, message, speaker_id, volume));
+        }
+    }
+
+    pub fn send_chat_to(&self, client_ids: &[u32], chat_type: ChatType, message: &str, speaker_id: i32, volume: i32) {
+        for event in self.chat_events(chat_type, message, speaker_id, volume) {
+            self.events.emit_to(client_ids, event);
+        }
     }
 }
@@ -0,0 +1,185 @@
+use std::time::{Duration, Instant};
+
+use dashmap::DashMap;
+
+use crate::{access::permissions::Role, app_state::{AppState, ChatType}, connection::ClientConnection, packets::utils::to_ascii};
+
+// How far chat carries with proximity_chat on, by the volume the client sent. Whisper, talk and yell.
+pub const CHAT_RANGES: [f32; 3] = [4.0, 24.0, 64.0];
+
+// chat_rate_limit is how many messages fit in this window.
+pub const CHAT_RATE_WINDOW: Duration = Duration::from_secs(10);
+
+#[derive(Default)]
+pub struct ChatManager {
+    // Account id to when it runs out, None is until someone unmutes them. Not saved, a restart clears them.
+    pub mutes: DashMap<u32, Option<Instant>>,
+}
+
+impl ChatManager {
+    pub fn mute(&self, account_id: u32, minutes: u32) {
+        let until = (minutes > 0).then(|| Instant::now() + Duration::from_secs(minutes as u64 * 60));
+
+        self.mutes.insert(account_id, until);
+    }
+
+    pub fn unmute(&self, account_id: u32) -> bool {
+        self.mutes.remove(&account_id).is_some()
+    }
+
+    pub fn is_muted(&self, account_id: u32) -> bool {
+        self.mutes.remove_if(&account_id, |_, until| until.is_some_and(|until| until <= Instant::now()));
+
+        self.mutes.contains_key(&account_id)
+    }
+}
+
+// Stars out every word in chat_filter, whatever case it's in. Only whole words, so filtering "ass" leaves "class" alone.
+// It works on the ASCII the client is sent, so an accent can't get a word past it and then be stripped off.
+pub fn filter_message(message: &str, filter: &str) -> String {
+    let mut filtered = to_ascii(message);
+    let lower = filtered.to_ascii_lowercase();
+    let bytes = lower.as_bytes();
+
+    let in_word = |index: Option<usize>| index.and_then(|index| bytes.get(index)).is_some_and(u8::is_ascii_alphanumeric);
+
+    for word in filter.split(',').map(|word| word.trim().to_ascii_lowercase()).filter(|word| !word.is_empty()) {
+        for (start, _) in lower.match_indices(&word) {
+            let end = start + word.len();
+
+            if !in_word(start.checked_sub(1)) && !in_word(Some(end)) {
+                filtered.replace_range(start..end, &"*".repeat(word.len()));
+            }
+        }
+    }
+
+    filtered
+}
+
+// Mutes and the spam limit, the error is what to tell them.
+pub fn check_can_chat(client: &mut ClientConnection, state: &AppState) -> Result<(), String> {
+    if state.chat.is_muted(client.account_id) {
+        return Err("You're muted.".to_string());
+    }
+
+    let limit = state.config().chat_rate_limit as usize;
+
+    if limit == 0 {
+        return Ok(());
+    }
+
+    client.chat_times.retain(|time| time.elapsed() < CHAT_RATE_WINDOW);
+
+    if client.chat_times.len() >= limit {
+        return Err("You're sending messages too fast.".to_string());
+    }
+
+    client.chat_times.push_back(Instant::now());
+
+    Ok(())
+}
+
+// Filtered and shown with who said it, for anything that isn't a plain announcement.
+fn format_message(name: &str, message: &str, state: &AppState) -> String {
+    format!("{name}: {}", filter_message(message, &state.config().chat_filter))
+}
+
+// Normal chat from the chat box, to everyone or just whoever is close enough to hear it.
+pub fn send_player_chat(client: &mut ClientConnection, message: &str, volume: u8, state: &AppState) {
+    if let Err(reason) = check_can_chat(client, state) {
+        state.send_chat(ChatType::PrivateMessage, &reason, client.client_id as i32, 0);
+
+        return;
+    }
+
+    let message = filter_message(message, &state.config().chat_filter);
+
+    chat_log!("{} ({}): {}", client.username, client.account_id, message);
+
+    if !state.config().proximity_chat {
+        state.send_chat(ChatType::Announce, &message, client.client_id as i32, volume as i32);
+
+        return;
+    }
+
+    let range = CHAT_RANGES[(volume as usize).min(CHAT_RANGES.len() - 1)];
+
+    let hearing = state
+        .connections
+        .iter()
+        .filter(|conn| (conn.camera_pos - client.camera_pos).magnitude() <= range)
+        .map(|conn| conn.client_id)
+        .collect::<Vec<u32>>();
+
+    state.send_chat_to(&hearing, ChatType::Announce, &message, client.client_id as i32, volume as i32);
+}
+
+pub fn send_team_chat(client: &mut ClientConnection, message: &str, state: &AppState) -> Result<(), String> {
+    check_can_chat(client, state)?;
+
+    let message = format_message(&client.username, message, state);
+
+    chat_log!("[{:?}] {} ({}): {}", client.team, client.username, client.account_id, message);
+
+    let team = state.connections.iter().filter(|conn| conn.team == client.team).map(|conn| conn.client_id).collect::<Vec<u32>>();
+
+    state.send_chat_to(&team, ChatType::Announce, &format!("[Team] {message}"), client.client_id as i32, 0);
+
+    Ok(())
+}
+
+// Returns the message as it was sent, after the filter.
+pub fn send_private_message(from: Option<&mut ClientConnection>, to: (u32, &str), message: &str, state: &AppState) -> Result<String, String> {
+    let name = match from {
+        Some(client) => {
+            check_can_chat(client, state)?;
+
+            client.username.clone()
+        }
+        None => "Console".to_string(),
+    };
+
+    let message = filter_message(message, &state.config().chat_filter);
+
+    let (client_id, to_name) = to;
+
+    chat_log!("[To {to_name}] {name}: {message}");
+
+    state.send_chat(ChatType::PrivateMessage, &format!("{name}: {message}"), client_id as i32, 0);
+
+    Ok(message)
+}
+
+// To every moderator and admin online.
+pub fn send_admin_chat(from: Option<&ClientConnection>, message: &str, state: &AppState) {
+    let name = from.map_or("Console".to_string(), |client| client.username.clone());
+    let message = format!("{name}: {message}");
+
+    chat_log!("[Admin] {message}");
+
+    let staff = state.connections.iter().filter(|conn| conn.role >= Role::Moderator).map(|conn| conn.client_id).collect::<Vec<u32>>();
+
+    state.send_chat_to(&staff, ChatType::AdminChat, &message, -1, 0);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn filter_stars_whole_words_in_any_case() {
+        assert_eq!(filter_message("You ASS, ass.", "ass"), "You ***, ***.");
+        assert_eq!(filter_message("bad word here", "bad word"), "******** here");
+    }
+
+    #[test]
+    fn filter_sees_through_accents() {
+        assert_eq!(filter_message("what the fück", "fuck"), "what the ****");
+        assert_eq!(filter_message("FÜCK", "fuck"), "****");
+    }
+
+    #[test]
+    fn filter_leaves_words_that_only_contain_one() {
+        assert_eq!(filter_message("class pass assign", "ass"), "class pass assign");
+    }
+}
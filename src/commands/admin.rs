@@ -0,0 +1,94 @@
+use crate::{
+    access::permissions::{role_for_account, Role},
+    commands::{args::ArgValues, CommandContext},
+    connection::{menu::menu_from_num, packets::{clientbound::initial_sync::ClientboundInitialSyncPacket, Encodable, GameState, Team}},
+};
+
+pub fn money(ctx: &mut CommandContext, args: &ArgValues) {
+    let state = ctx.state;
+    let client = ctx.player();
+
+    client.money = args.int(0).map_or(10000, |money| money.clamp(i32::MIN as i64, i32::MAX as i64) as i32);
+    client.update_money(state);
+}
+
+pub fn state(ctx: &mut CommandContext, args: &ArgValues) {
+    let new = match args.choice(0) {
+        Some("ingame") => GameState::InGame,
+        Some("intermission") => GameState::Intermission,
+        Some("idle") => GameState::Idle,
+        Some("restarting") => GameState::Restarting,
+        _ => ctx.state.game_state(),
+    };
+
+    ctx.state.set_game_state(new);
+}
+
+pub fn door(ctx: &mut CommandContext, args: &ArgValues) {
+    let team = args.choice(0).and_then(|team| team.parse().ok()).unwrap_or(Team::Goldmen);
+    let open = args.choice(1) == Some("open");
+
+    ctx.state.set_team_door(team, open);
+    ctx.reply(&format!("{team:?}'s door is now {}.", if open { "open" } else { "closed" }));
+}
+
+pub fn menu(ctx: &mut CommandContext, args: &ArgValues) {
+    ctx.player().menu = menu_from_num(args.int(0).map_or(0, |menu| menu.clamp(0, u8::MAX as i64) as u8));
+}
+
+pub fn loadmap(ctx: &mut CommandContext, args: &ArgValues) {
+    let map = args.text(0).unwrap_or("test2");
+
+    *ctx.state.map_name.write().unwrap() = map.to_string();
+
+    let event = ClientboundInitialSyncPacket {
+        round_number: ctx.state.round_number(),
+        sun_angle: 0,
+        sun_axial_tilt: 0,
+        versus_movedelay: None,
+        weekday: 0,
+        weekly_enabled: false
+    };
+
+    ctx.state.broadcast_packet(event.encode(ctx.state));
+}
+
+// Saved with the account, the config lists can still give someone more than this.
+pub fn role(ctx: &mut CommandContext, args: &ArgValues) {
+    let state = ctx.state;
+    let (Some(account_id), Some(role)) = (args.account(0), args.choice(1).and_then(|role| role.parse::<Role>().ok())) else {
+        return;
+    };
+
+    let (name, effective) = {
+        let mut data = state.player_store.lock().unwrap();
+
+        let Some(mut account) = data.get_account(account_id) else {
+            drop(data);
+            ctx.reply("They have never played here.");
+
+            return;
+        };
+
+        account.role = role;
+        data.update_account(&account);
+        data.save();
+
+        (account.name(), role_for_account(&state.config(), data.as_ref(), account_id))
+    };
+
+    // Demoting someone also takes away an admin /login they have this session. Our own connection is the copy in ctx.
+    if let Some(client) = ctx.client.as_deref_mut()
+        && client.account_id == account_id
+    {
+        client.role = effective;
+    } else if let Some(mut connection) = state.connections.iter_mut().find(|conn| conn.account_id == account_id) {
+        connection.role = effective;
+    }
+
+    if effective == role {
+        ctx.reply(&format!("{name}'s role is now {role}."));
+    } else {
+        ctx.reply(&format!("Saved {name}'s role as {role}, but the config gives them {effective}."));
+    }
+}
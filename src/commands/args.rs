@@ -0,0 +1,177 @@
+use std::net::SocketAddr;
+
+use crate::{app_state::AppState, world::vector::Vector};
+
+#[derive(Debug, Clone, Copy)]
+pub enum ArgType {
+    // Someone online, by client id, name or a unique name prefix.
+    Player,
+    // Someone online, or anyone in the player store by account id or name.
+    Account,
+    Int,
+    Float,
+    // Three numbers, x y z.
+    Vector,
+    Choice(&'static [&'static str]),
+    Word,
+    // The rest of the line, has to be the last argument.
+    Text,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Arg {
+    pub name: &'static str,
+    pub kind: ArgType,
+    pub optional: bool,
+}
+
+impl Arg {
+    pub const fn required(name: &'static str, kind: ArgType) -> Self {
+        Self { name, kind, optional: false }
+    }
+
+    pub const fn optional(name: &'static str, kind: ArgType) -> Self {
+        Self { name, kind, optional: true }
+    }
+
+    pub fn usage(&self) -> String {
+        let name = match self.kind {
+            ArgType::Choice(choices) => choices.join("|"),
+            ArgType::Vector => format!("{0}.x {0}.y {0}.z", self.name),
+            ArgType::Text => format!("{}...", self.name),
+            _ => self.name.to_string(),
+        };
+
+        if self.optional { format!("[{name}]") } else { format!("<{name}>") }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum ArgValue {
+    Player(SocketAddr),
+    Account(u32),
+    Int(i64),
+    Float(f32),
+    Vector(Vector),
+    Choice(&'static str),
+    Text(String),
+}
+
+// Parsed arguments, in the same order as the command declares them. Optional ones that weren't given are None.
+#[derive(Debug, Clone, Default)]
+pub struct ArgValues(pub Vec<Option<ArgValue>>);
+
+impl ArgValues {
+    pub fn player(&self, index: usize) -> Option<SocketAddr> {
+        match self.0.get(index)? {
+            Some(ArgValue::Player(address)) => Some(*address),
+            _ => None,
+        }
+    }
+
+    pub fn account(&self, index: usize) -> Option<u32> {
+        match self.0.get(index)? {
+            Some(ArgValue::Account(account_id)) => Some(*account_id),
+            _ => None,
+        }
+    }
+
+    pub fn int(&self, index: usize) -> Option<i64> {
+        match self.0.get(index)? {
+            Some(ArgValue::Int(value)) => Some(*value),
+            _ => None,
+        }
+    }
+
+    pub fn float(&self, index: usize) -> Option<f32> {
+        match self.0.get(index)? {
+            Some(ArgValue::Float(value)) => Some(*value),
+            _ => None,
+        }
+    }
+
+    pub fn vector(&self, index: usize) -> Option<Vector> {
+        match self.0.get(index)? {
+            Some(ArgValue::Vector(value)) => Some(*value),
+            _ => None,
+        }
+    }
+
+    pub fn choice(&self, index: usize) -> Option<&'static str> {
+        match self.0.get(index)? {
+            Some(ArgValue::Choice(value)) => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn text(&self, index: usize) -> Option<&str> {
+        match self.0.get(index)? {
+            Some(ArgValue::Text(value)) => Some(value),
+            _ => None,
+        }
+    }
+}
+
+fn parse_float(word: &str, name: &str) -> Result<f32, String> {
+    word.parse::<f32>().map_err(|_| format!("{name} has to be a number, not {word}."))
+}
+
+fn parse_value(kind: ArgType, name: &str, words: &[&str], state: &AppState) -> Result<ArgValue, String> {
+    let word = words[0];
+
+    match kind {
+        ArgType::Player => state.find_connection(word).map(ArgValue::Player).ok_or(format!("Couldn't find a player called {word}.")),
+        ArgType::Account => state
+            .find_connection(word)
+            .and_then(|addr| state.connections.get(&addr).map(|conn| conn.account_id))
+            .or_else(|| state.player_store.lock().unwrap().find_account(word))
+            .map(ArgValue::Account)
+            .ok_or(format!("Couldn't find a player called {word}.")),
+        ArgType::Int => word.parse::<i64>().map(ArgValue::Int).map_err(|_| format!("{name} has to be a whole number, not {word}.")),
+        ArgType::Float => parse_float(word, name).map(ArgValue::Float),
+        ArgType::Vector => Ok(ArgValue::Vector(Vector::new(
+            parse_float(words[0], name)?,
+            parse_float(words[1], name)?,
+            parse_float(words[2], name)?,
+        ))),
+        ArgType::Choice(choices) => choices
+            .iter()
+            .find(|choice| choice.eq_ignore_ascii_case(word))
+            .map(|choice| ArgValue::Choice(choice))
+            .ok_or(format!("{name} has to be one of {}.", choices.join(", "))),
+        ArgType::Word => Ok(ArgValue::Text(word.to_string())),
+        ArgType::Text => Ok(ArgValue::Text(words.join(" "))),
+    }
+}
+
+// Errors are the message to send back, the caller adds the usage line.
+pub fn parse_args(args: &[Arg], words: &[&str], state: &AppState) -> Result<ArgValues, String> {
+    let mut values = ArgValues::default();
+    let mut rest = words;
+
+    for arg in args {
+        let count = match arg.kind {
+            ArgType::Vector => 3,
+            ArgType::Text => rest.len(),
+            _ => 1,
+        };
+
+        if rest.is_empty() || rest.len() < count {
+            if !arg.optional {
+                return Err(format!("Missing {}.", arg.name));
+            }
+
+            values.0.push(None);
+            continue;
+        }
+
+        values.0.push(Some(parse_value(arg.kind, arg.name, &rest[..count], state)?));
+        rest = &rest[count..];
+    }
+
+    if !rest.is_empty() {
+        return Err("Too many arguments.".to_string());
+    }
+
+    Ok(values)
+}
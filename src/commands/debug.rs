@@ -0,0 +1,58 @@
+use crate::{
+    app_state::ChatType,
+    commands::{args::ArgValues, CommandContext},
+    world::quaternion::Quaternion,
+};
+
+pub fn campos(ctx: &mut CommandContext, _: &ArgValues) {
+    let camera_pos = ctx.player().camera_pos;
+
+    ctx.reply(&format!("{camera_pos:?}"));
+}
+
+pub fn itemtype(ctx: &mut CommandContext, args: &ArgValues) {
+    let mut typ = args.int(0).unwrap_or(45);
+
+    if typ == 39 || typ == 18 || typ == 26 || !(0..=45).contains(&typ) {
+        typ = 45
+    }
+
+    for mut item in ctx.state.items.items.iter_mut() {
+        item.item_type = typ as u32;
+    }
+}
+
+pub fn debugplayer(ctx: &mut CommandContext, _: &ArgValues) {
+    let state = ctx.state;
+    let client = ctx.player();
+
+    info!("debug", "Con {client:?}");
+
+    if let Some(event) = state.events.players.get(&client.client_id) {
+        info!("debug", "Eve {:?}", *event);
+    }
+
+    ctx.reply("Printed player struct to terminal.");
+}
+
+pub fn car(ctx: &mut CommandContext, args: &ArgValues) {
+    let rot = args.vector(0).unwrap_or_default();
+
+    let mut car = ctx.state.vehicles.vehicles.get_mut(&0).unwrap();
+
+    car.transform.rot = Quaternion::euler(rot.x, rot.y, rot.z).normalized();
+}
+
+pub fn carrot(ctx: &mut CommandContext, _: &ArgValues) {
+    let car = ctx.state.vehicles.vehicles.get(&0).unwrap();
+
+    info!("debug", "ROt {:?}", car.transform.rot);
+
+    ctx.state.send_chat(ChatType::Announce, &format!("{:?}", car.transform.rot), -1, 0);
+}
+
+pub fn valid(ctx: &mut CommandContext, _: &ArgValues) {
+    let car = ctx.state.vehicles.vehicles.get(&0).unwrap();
+
+    ctx.state.send_chat(ChatType::Announce, &format!("{:?}", car.transform.rot.is_valid()), -1, 0);
+}
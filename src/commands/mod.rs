@@ -1,120 +1,555 @@
-use crate::{app_state::{AppState, ChatType}, connection::{menu::menu_from_num, packets::{clientbound::initial_sync::ClientboundInitialSyncPacket, Encodable, GameState}, ClientConnection}, world::quaternion::Quaternion};
-
-pub fn parse_command(client: &mut ClientConnection, message: String, state: &AppState) -> bool {
-    if !message.starts_with('/') {
-        return false
+use tokio::sync::mpsc::UnboundedSender;
+
+use crate::{
+    access::permissions::Role,
+    app_state::{AppState, ChatType},
+    commands::args::{parse_args, Arg, ArgType, ArgValues},
+    connection::ClientConnection,
+};
+
+pub mod admin;
+pub mod args;
+pub mod debug;
+pub mod moderation;
+pub mod player;
+pub mod server;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Scope {
+    Anywhere,
+    // Needs a player to act on.
+    InGame,
+    Console,
+}
+
+pub struct CommandContext<'a> {
+    // None when the command came from the server console.
+    pub client: Option<&'a mut ClientConnection>,
+    pub state: &'a AppState,
+    // Where replies go when there's no player, RCON sets this. Otherwise they're logged.
+    pub output: Option<UnboundedSender<String>>,
+}
+
+impl CommandContext<'_> {
+    // The console can do anything.
+    pub fn role(&self) -> Role {
+        self.client.as_ref().map_or(Role::Admin, |client| client.role)
     }
 
-    let command = message.split(' ').collect::<Vec<&str>>()[0].replace('/', "");
-    let args = message.split(' ').collect::<Vec<&str>>()[1..].to_vec().iter().map(|s| s.to_string()).collect::<Vec<String>>();
+    pub fn is_console(&self) -> bool {
+        self.client.is_none()
+    }
 
-    match command.as_str() {
-        "nick" => {
-            client.username = args.join(" ").to_string();
+    // Only for InGame commands, which never run without a player.
+    pub fn player(&mut self) -> &mut ClientConnection {
+        self.client.as_deref_mut().expect("In game commands always have a player")
+    }
 
-            client.update_player(state);
+    pub fn reply(&self, message: &str) {
+        match &self.client {
+            Some(client) => self.state.send_chat(ChatType::PrivateMessage, message, client.client_id as i32, 0),
+            None => match &self.output {
+                Some(output) => {
+                    let _ = output.send(message.to_string());
+                }
+                None => info!("console", "{message}"),
+            },
         }
+    }
 
-        "money" => {
-            client.money = args.first().unwrap_or(&"10000".to_string()).parse::<i32>().unwrap_or(10000);
-
-            client.update_money(state);
-        }
+    pub fn can_use(&self, command: &Command) -> bool {
+        let scope = match command.scope {
+            Scope::Anywhere => true,
+            Scope::InGame => !self.is_console(),
+            Scope::Console => self.is_console(),
+        };
 
-        "state" => {
-            let new = match args.first().unwrap_or(&"".to_string()).to_lowercase().as_str() {
-                "ingame" => GameState::InGame,
-                "intermission" => GameState::Intermission,
-                "idle" => GameState::Idle,
-                "restarting" => GameState::Restarting,
-                _ => state.game_state()
-            };
-
-            let mut write = state.game_state.state.write().unwrap();
-            *write = new;
+        scope && self.role() >= command.role
+    }
+}
+
+pub struct Command {
+    pub name: &'static str,
+    pub aliases: &'static [&'static str],
+    pub args: &'static [Arg],
+    pub role: Role,
+    pub scope: Scope,
+    pub description: &'static str,
+    pub handler: fn(&mut CommandContext, &ArgValues),
+}
+
+impl Command {
+    pub fn usage(&self) -> String {
+        let mut usage = format!("/{}", self.name);
+
+        for arg in self.args {
+            usage.push(' ');
+            usage.push_str(&arg.usage());
         }
 
-        "menu" => {
-            let menu_type = menu_from_num(args.first().unwrap_or(&"0".to_string()).parse::<u8>().unwrap_or(0));
-
-            client.menu = menu_type;
-        }
+        usage
+    }
+}
+
+pub const COMMANDS: &[Command] = &[
+    Command {
+        name: "help",
+        aliases: &["?", "commands"],
+        args: &[Arg::optional("command", ArgType::Word)],
+        role: Role::Player,
+        scope: Scope::Anywhere,
+        description: "Lists the commands you can use, or explains one.",
+        handler: help,
+    },
+    Command {
+        name: "login",
+        aliases: &[],
+        args: &[Arg::required("password", ArgType::Text)],
+        role: Role::Player,
+        scope: Scope::InGame,
+        description: "Logs in as an admin with the admin password.",
+        handler: player::login,
+    },
+    Command {
+        name: "nick",
+        aliases: &[],
+        args: &[Arg::required("name", ArgType::Text)],
+        role: Role::Player,
+        scope: Scope::InGame,
+        description: "Changes your name.",
+        handler: player::nick,
+    },
+    Command {
+        name: "customize",
+        aliases: &["look"],
+        args: &[
+            Arg::optional("part or number", ArgType::Word),
+            Arg::optional("value|next|prev", ArgType::Word),
+        ],
+        role: Role::Player,
+        scope: Scope::InGame,
+        description: "Shows or changes how your character looks.",
+        handler: player::customize,
+    },
+    Command {
+        name: "msg",
+        aliases: &["pm", "w"],
+        args: &[Arg::required("player", ArgType::Player), Arg::required("message", ArgType::Text)],
+        role: Role::Player,
+        scope: Scope::Anywhere,
+        description: "Sends a private message.",
+        handler: player::msg,
+    },
+    Command {
+        name: "team",
+        aliases: &["t"],
+        args: &[Arg::required("message", ArgType::Text)],
+        role: Role::Player,
+        scope: Scope::InGame,
+        description: "Talks to your team only.",
+        handler: player::team,
+    },
+    Command {
+        name: "stocks",
+        aliases: &[],
+        args: &[],
+        role: Role::Player,
+        scope: Scope::Anywhere,
+        description: "Shows the share price of every corporation.",
+        handler: player::stocks,
+    },
+    Command {
+        name: "stats",
+        aliases: &[],
+        args: &[],
+        role: Role::Player,
+        scope: Scope::InGame,
+        description: "Shows your stats for this game mode.",
+        handler: player::stats,
+    },
+    Command {
+        name: "corp",
+        aliases: &[],
+        args: &[
+            Arg::optional("action", ArgType::Choice(&["apply", "hire", "fire", "team", "requisition"])),
+            Arg::optional("corporation or number", ArgType::Word),
+        ],
+        role: Role::Player,
+        scope: Scope::InGame,
+        description: "Applies to a corporation, or hires, fires and requisitions for yours. Without a number it lists the choices.",
+        handler: player::corp,
+    },
+    Command {
+        name: "quitcorp",
+        aliases: &[],
+        args: &[],
+        role: Role::Player,
+        scope: Scope::InGame,
+        description: "Quits the corporation you work for.",
+        handler: player::quitcorp,
+    },
+    Command {
+        name: "adminchat",
+        aliases: &["a"],
+        args: &[Arg::required("message", ArgType::Text)],
+        role: Role::Moderator,
+        scope: Scope::Anywhere,
+        description: "Talks to the other moderators and admins.",
+        handler: moderation::adminchat,
+    },
+    Command {
+        name: "mute",
+        aliases: &[],
+        args: &[Arg::required("player", ArgType::Account), Arg::optional("minutes", ArgType::Int)],
+        role: Role::Moderator,
+        scope: Scope::Anywhere,
+        description: "Stops a player from chatting, for good if no minutes are given.",
+        handler: moderation::mute,
+    },
+    Command {
+        name: "unmute",
+        aliases: &[],
+        args: &[Arg::required("player", ArgType::Account)],
+        role: Role::Moderator,
+        scope: Scope::Anywhere,
+        description: "Lets a muted player chat again.",
+        handler: moderation::unmute,
+    },
+    Command {
+        name: "voicemute",
+        aliases: &["vmute"],
+        args: &[Arg::required("player", ArgType::Account)],
+        role: Role::Moderator,
+        scope: Scope::Anywhere,
+        description: "Stops a player's voice chat reaching anyone.",
+        handler: moderation::voicemute,
+    },
+    Command {
+        name: "voiceunmute",
+        aliases: &["vunmute"],
+        args: &[Arg::required("player", ArgType::Account)],
+        role: Role::Moderator,
+        scope: Scope::Anywhere,
+        description: "Lets a voice muted player be heard again.",
+        handler: moderation::voiceunmute,
+    },
+    Command {
+        name: "kick",
+        aliases: &[],
+        args: &[Arg::required("player", ArgType::Player), Arg::optional("reason", ArgType::Text)],
+        role: Role::Moderator,
+        scope: Scope::Anywhere,
+        description: "Kicks a player.",
+        handler: moderation::kick,
+    },
+    Command {
+        name: "ban",
+        aliases: &[],
+        args: &[Arg::required("player", ArgType::Account), Arg::optional("minutes", ArgType::Int), Arg::optional("reason", ArgType::Text)],
+        role: Role::Moderator,
+        scope: Scope::Anywhere,
+        description: "Bans an account, forever if no minutes are given.",
+        handler: moderation::ban,
+    },
+    Command {
+        name: "unban",
+        aliases: &[],
+        args: &[Arg::required("player", ArgType::Account)],
+        role: Role::Moderator,
+        scope: Scope::Anywhere,
+        description: "Lifts an account ban.",
+        handler: moderation::unban,
+    },
+    Command {
+        name: "banip",
+        aliases: &[],
+        args: &[Arg::required("ip[/prefix]", ArgType::Word), Arg::optional("minutes", ArgType::Int), Arg::optional("reason", ArgType::Text)],
+        role: Role::Admin,
+        scope: Scope::Anywhere,
+        description: "Bans an IP range, forever if no minutes are given.",
+        handler: moderation::banip,
+    },
+    Command {
+        name: "unbanip",
+        aliases: &[],
+        args: &[Arg::required("ip[/prefix]", ArgType::Word)],
+        role: Role::Admin,
+        scope: Scope::Anywhere,
+        description: "Lifts an IP range ban.",
+        handler: moderation::unbanip,
+    },
+    Command {
+        name: "whitelist",
+        aliases: &["wl"],
+        args: &[
+            Arg::required("action", ArgType::Choice(&["on", "off", "list", "add", "remove", "addsteam", "removesteam"])),
+            Arg::optional("player, account id or steam id", ArgType::Text),
+        ],
+        role: Role::Admin,
+        scope: Scope::Anywhere,
+        description: "Manages who can join while the whitelist is on.",
+        handler: moderation::whitelist,
+    },
+    Command {
+        name: "money",
+        aliases: &[],
+        args: &[Arg::optional("amount", ArgType::Int)],
+        role: Role::Admin,
+        scope: Scope::InGame,
+        description: "Sets your money.",
+        handler: admin::money,
+    },
+    Command {
+        name: "state",
+        aliases: &[],
+        args: &[Arg::required("state", ArgType::Choice(&["ingame", "intermission", "idle", "restarting"]))],
+        role: Role::Admin,
+        scope: Scope::Anywhere,
+        description: "Changes the game state.",
+        handler: admin::state,
+    },
+    Command {
+        name: "door",
+        aliases: &[],
+        args: &[
+            Arg::required("team", ArgType::Choice(&["goldmen", "monsota", "oxs", "nexaco", "pentacom"])),
+            Arg::required("state", ArgType::Choice(&["open", "closed"])),
+        ],
+        role: Role::Admin,
+        scope: Scope::Anywhere,
+        description: "Opens or closes a team's door.",
+        handler: admin::door,
+    },
+    Command {
+        name: "role",
+        aliases: &[],
+        args: &[Arg::required("player", ArgType::Account), Arg::required("role", ArgType::Choice(&["player", "moderator", "admin"]))],
+        role: Role::Admin,
+        scope: Scope::Anywhere,
+        description: "Saves a player's role with their account.",
+        handler: admin::role,
+    },
+    Command {
+        name: "menu",
+        aliases: &[],
+        args: &[Arg::optional("menu", ArgType::Int)],
+        role: Role::Admin,
+        scope: Scope::InGame,
+        description: "Opens a menu by number.",
+        handler: admin::menu,
+    },
+    Command {
+        name: "loadmap",
+        aliases: &[],
+        args: &[Arg::optional("map", ArgType::Word)],
+        role: Role::Admin,
+        scope: Scope::Anywhere,
+        description: "Loads a map for everyone.",
+        handler: admin::loadmap,
+    },
+    Command {
+        name: "campos",
+        aliases: &[],
+        args: &[],
+        role: Role::Admin,
+        scope: Scope::InGame,
+        description: "Shows your camera position.",
+        handler: debug::campos,
+    },
+    Command {
+        name: "itemtype",
+        aliases: &[],
+        args: &[Arg::optional("type", ArgType::Int)],
+        role: Role::Admin,
+        scope: Scope::Anywhere,
+        description: "Changes the type of every item.",
+        handler: debug::itemtype,
+    },
+    Command {
+        name: "debugplayer",
+        aliases: &[],
+        args: &[],
+        role: Role::Admin,
+        scope: Scope::InGame,
+        description: "Prints your connection to the server console.",
+        handler: debug::debugplayer,
+    },
+    Command {
+        name: "car",
+        aliases: &[],
+        args: &[Arg::required("rotation", ArgType::Vector)],
+        role: Role::Admin,
+        scope: Scope::Anywhere,
+        description: "Rotates the test car.",
+        handler: debug::car,
+    },
+    Command {
+        name: "carrot",
+        aliases: &[],
+        args: &[],
+        role: Role::Admin,
+        scope: Scope::Anywhere,
+        description: "Shows the test car's rotation.",
+        handler: debug::carrot,
+    },
+    Command {
+        name: "valid",
+        aliases: &[],
+        args: &[],
+        role: Role::Admin,
+        scope: Scope::Anywhere,
+        description: "Checks the test car's rotation is valid.",
+        handler: debug::valid,
+    },
+    Command {
+        name: "status",
+        aliases: &[],
+        args: &[],
+        role: Role::Admin,
+        scope: Scope::Console,
+        description: "Shows the game state and who is online.",
+        handler: server::status,
+    },
+    Command {
+        name: "players",
+        aliases: &[],
+        args: &[],
+        role: Role::Admin,
+        scope: Scope::Console,
+        description: "Lists who is online, one player per line split by tabs.",
+        handler: server::players,
+    },
+    Command {
+        name: "say",
+        aliases: &[],
+        args: &[Arg::required("message", ArgType::Text)],
+        role: Role::Admin,
+        scope: Scope::Console,
+        description: "Sends a message to everyone.",
+        handler: server::say,
+    },
+    Command {
+        name: "save",
+        aliases: &[],
+        args: &[],
+        role: Role::Admin,
+        scope: Scope::Console,
+        description: "Saves every player now.",
+        handler: server::save,
+    },
+    Command {
+        name: "reloadconfig",
+        aliases: &[],
+        args: &[],
+        role: Role::Admin,
+        scope: Scope::Console,
+        description: "Rereads config.txt.",
+        handler: server::reloadconfig,
+    },
+    Command {
+        name: "shutdown",
+        aliases: &["stop", "quit"],
+        args: &[],
+        role: Role::Admin,
+        scope: Scope::Console,
+        description: "Saves, kicks everyone and stops the server.",
+        handler: server::shutdown,
+    },
+];
+
+// Exact names and aliases first, then a unique prefix of something this sender can use.
+pub fn find_command(name: &str, ctx: &CommandContext) -> Result<&'static Command, String> {
+    let name = name.to_lowercase();
+
+    if let Some(command) = COMMANDS.iter().find(|command| command.name == name || command.aliases.contains(&name.as_str()))
+        && (command.scope != Scope::Console || ctx.is_console())
+    {
+        return Ok(command);
+    }
 
-        "loadmap" => {
-            let d = "test2".to_string();
-            let map = args.first().unwrap_or(&d);
+    let matches = COMMANDS.iter().filter(|command| ctx.can_use(command) && command.name.starts_with(&name)).collect::<Vec<&Command>>();
 
-            {
-                let mut name = state.map_name.write().unwrap();
-                *name = map.to_string();
+    match matches.as_slice() {
+        [command] => Ok(command),
+        [] => Err(format!("Unknown command /{name}.")),
+        _ => Err(format!("Did you mean {}?", matches.iter().map(|command| format!("/{}", command.name)).collect::<Vec<String>>().join(", "))),
+    }
+}
 
-                drop(name)
-            }
+// Runs a command line without the leading slash. False if there was nothing to run.
+pub fn run_command(ctx: &mut CommandContext, line: &str) -> bool {
+    let words = line.split_whitespace().collect::<Vec<&str>>();
 
-            let event = ClientboundInitialSyncPacket {
-                round_number: state.round_number(),
-                sun_angle: 0,
-                sun_axial_tilt: 0,
-                versus_movedelay: None,
-                weekday: 0,
-                weekly_enabled: false
-            };
+    let Some(name) = words.first() else {
+        return false
+    };
 
-            state.broadcast_packet(event.encode(state));
-        }
+    let command = match find_command(name, ctx) {
+        Ok(command) => command,
+        Err(message) => {
+            ctx.reply(&message);
 
-        "campos" => {
-            state.send_chat(ChatType::PrivateMessage, &format!("{:?}", client.camera_pos), client.client_id as i32, 0);
+            return true;
         }
+    };
 
-        "itemtype" => {
-            let mut typ = args.first().unwrap_or(&"45".to_string()).parse::<u32>().unwrap_or(45);
+    if command.scope == Scope::InGame && ctx.is_console() {
+        ctx.reply(&format!("/{} can only be used in game.", command.name));
 
-            if typ == 39 || typ == 18 || typ == 26 || !(0..=45).contains(&typ) {
-                typ = 45
-            }
+        return true;
+    }
 
-            for mut item in state.items.items.iter_mut() {
-                item.item_type = typ;
-            }
-        }
+    if ctx.role() < command.role {
+        ctx.reply(&format!("You need to be a {} to use /{}.", command.role, command.name));
 
-        "debugplayer" => {
-            println!("Con {client:?}");
+        return true;
+    }
 
-            if let Some(event) = state.events.players.get(&client.client_id) {
-                println!("Eve {:?}", *event);
-            }
+    // Anything past what every player can do ends up in the audit log.
+    if command.role > Role::Player {
+        let source = match (&ctx.client, &ctx.output) {
+            (Some(client), _) => format!("{} ({})", client.username, client.account_id),
+            (None, Some(_)) => "RCON".to_string(),
+            (None, None) => "Console".to_string(),
+        };
 
-            state.send_chat(ChatType::PrivateMessage, "Printed player struct to terminal.", client.client_id as i32, 0);
-        }
+        audit_log!("{source} ran /{}", words.join(" "));
+    }
 
-        "car" => {
-            let x = args.first().unwrap_or(&"0".to_string()).parse::<f32>().unwrap_or(1.0);
-            let y = args.get(1).unwrap_or(&"0".to_string()).parse::<f32>().unwrap_or(1.0);
-            let z = args.get(2).unwrap_or(&"0".to_string()).parse::<f32>().unwrap_or(1.0);
+    match parse_args(command.args, &words[1..], ctx.state) {
+        Ok(args) => (command.handler)(ctx, &args),
+        Err(message) => ctx.reply(&format!("{message} Usage: {}", command.usage())),
+    }
 
-            let mut car = state.vehicles.vehicles.get_mut(&0).unwrap();
+    true
+}
 
-            car.transform.rot = Quaternion::euler(x, y, z).normalized();
-        }
+pub fn parse_command(client: &mut ClientConnection, message: String, state: &AppState) -> bool {
+    let Some(message) = message.strip_prefix('/') else {
+        return false
+    };
 
-        "carrot" => {
-            let car = state.vehicles.vehicles.get(&0).unwrap();
+    run_command(&mut CommandContext { client: Some(client), state, output: None }, message)
+}
 
-            println!("ROt {:?}", car.transform.rot);
+fn help(ctx: &mut CommandContext, args: &ArgValues) {
+    if !ctx.is_console() && !ctx.state.config().help {
+        ctx.reply("Help is turned off on this server.");
 
-            state.send_chat(ChatType::Announce, &format!("{:?}", car.transform.rot), -1, 0);
-        }
+        return;
+    }
 
-        "valid" => {
-            let car = state.vehicles.vehicles.get(&0).unwrap();
+    let message = match args.text(0) {
+        Some(name) => match find_command(name.trim_start_matches('/'), ctx) {
+            Ok(command) => format!("{} - {}", command.usage(), command.description),
+            Err(message) => message,
+        },
+        None => {
+            let names = COMMANDS.iter().filter(|command| ctx.can_use(command)).map(|command| format!("/{}", command.name)).collect::<Vec<String>>();
 
-            state.send_chat(ChatType::Announce, &format!("{:?}", car.transform.rot.is_valid()), -1, 0);
+            format!("Commands: {}. Use /help <command> for more.", names.join(", "))
         }
+    };
 
-        _ => return false
-    }  
-
-    true  
-}
\ No newline at end of file
+    ctx.reply(&message);
+}
@@ -0,0 +1,212 @@
+use crate::{
+    access::bans::parse_ip_range,
+    chat::send_admin_chat,
+    commands::{args::ArgValues, CommandContext},
+};
+
+pub fn kick(ctx: &mut CommandContext, args: &ArgValues) {
+    let Some((client_id, name)) = args.player(0).and_then(|addr| ctx.state.connections.get(&addr).map(|conn| (conn.client_id, conn.username.clone()))) else {
+        return;
+    };
+
+    let reason = args.text(1).unwrap_or("Kicked by an admin.");
+
+    if ctx.state.kick(client_id, reason) {
+        ctx.reply(&format!("Kicked {name}."));
+    }
+}
+
+pub fn ban(ctx: &mut CommandContext, args: &ArgValues) {
+    let Some(account_id) = args.account(0) else {
+        return;
+    };
+
+    let minutes = args.int(1).map_or(0, |minutes| minutes.clamp(0, u32::MAX as i64) as u32);
+    let reason = args.text(2).unwrap_or_default();
+
+    let online = ctx.state.connections.iter().find(|conn| conn.account_id == account_id).map(|conn| conn.client_id);
+
+    let mut data = ctx.state.player_store.lock().unwrap();
+    ctx.state.bans.ban_account(data.as_mut(), account_id, minutes, reason);
+
+    let message = ctx.state.bans.account_ban_message(data.as_ref(), account_id).unwrap_or_default();
+    let name = data.account_name(account_id);
+
+    drop(data);
+
+    if let Some(client_id) = online {
+        ctx.state.kick(client_id, &message);
+    }
+
+    ctx.reply(&format!("{name} {}", message.replacen("Banned", "banned", 1)));
+}
+
+pub fn unban(ctx: &mut CommandContext, args: &ArgValues) {
+    let Some(account_id) = args.account(0) else {
+        return;
+    };
+
+    let mut data = ctx.state.player_store.lock().unwrap();
+    ctx.state.bans.unban_account(data.as_mut(), account_id);
+
+    let message = format!("Unbanned {}.", data.account_name(account_id));
+
+    drop(data);
+
+    ctx.reply(&message);
+}
+
+pub fn banip(ctx: &mut CommandContext, args: &ArgValues) {
+    let Some((network, prefix)) = args.text(0).and_then(parse_ip_range) else {
+        ctx.reply("That isn't an IP address or range.");
+
+        return;
+    };
+
+    let minutes = args.int(1).map_or(0, |minutes| minutes.clamp(0, u32::MAX as i64) as u32);
+    let reason = args.text(2).unwrap_or_default();
+
+    ctx.state.bans.ban_range(ctx.state.player_store.lock().unwrap().as_mut(), network, prefix, minutes, reason);
+
+    let own_id = ctx.client.as_ref().map(|client| client.client_id);
+
+    let banned = ctx
+        .state
+        .connections
+        .iter()
+        .filter(|conn| Some(conn.client_id) != own_id)
+        .filter_map(|conn| ctx.state.bans.check(ctx.state.player_store.lock().unwrap().as_ref(), conn.account_id, conn.address.ip()).map(|message| (conn.client_id, message)))
+        .collect::<Vec<(u32, String)>>();
+
+    for (client_id, message) in &banned {
+        ctx.state.kick(*client_id, message);
+    }
+
+    ctx.reply(&format!("Banned {network}/{prefix}, kicked {} players.", banned.len()));
+}
+
+pub fn unbanip(ctx: &mut CommandContext, args: &ArgValues) {
+    let message = match args.text(0).and_then(parse_ip_range) {
+        Some((network, prefix)) if ctx.state.bans.unban_range(ctx.state.player_store.lock().unwrap().as_mut(), network, prefix) => {
+            format!("Unbanned {network}/{prefix}.")
+        }
+        Some((network, prefix)) => format!("{network}/{prefix} wasn't banned."),
+        None => "That isn't an IP address or range.".to_string(),
+    };
+
+    ctx.reply(&message);
+}
+
+pub fn whitelist(ctx: &mut CommandContext, args: &ArgValues) {
+    let state = ctx.state;
+    let target = args.text(1).unwrap_or_default();
+
+    // Online players first, then anyone in the player store, and any other number is taken as an account id.
+    let account_id = state
+        .find_connection(target)
+        .and_then(|addr| state.connections.get(&addr).map(|conn| conn.account_id))
+        .or_else(|| state.player_store.lock().unwrap().find_account(target))
+        .or_else(|| target.parse::<u32>().ok());
+    let steam_id = target.parse::<u64>().ok();
+
+    let message = match (args.choice(0), account_id, steam_id) {
+        (Some("on"), _, _) => {
+            state.whitelist.set_enabled(true);
+
+            "The whitelist is now on.".to_string()
+        }
+        (Some("off"), _, _) => {
+            state.whitelist.set_enabled(false);
+
+            "The whitelist is now off.".to_string()
+        }
+        (Some("list"), _, _) => {
+            let list = state.whitelist.list.read().unwrap();
+            let data = state.player_store.lock().unwrap();
+
+            let accounts = list.account_ids.iter().map(|id| format!("{} ({id})", data.account_name(*id))).collect::<Vec<String>>();
+            let steam_ids = list.steam_ids.iter().map(u64::to_string).collect::<Vec<String>>();
+
+            format!(
+                "Whitelist is {}. Accounts: {}. Steam ids: {}.",
+                if state.whitelist.is_enabled() { "on" } else { "off" },
+                if accounts.is_empty() { "none".to_string() } else { accounts.join(", ") },
+                if steam_ids.is_empty() { "none".to_string() } else { steam_ids.join(", ") }
+            )
+        }
+        (Some("add"), Some(account_id), _) if state.whitelist.add_account(account_id) => format!("Whitelisted account {account_id}."),
+        (Some("add"), Some(account_id), _) => format!("Account {account_id} is already whitelisted."),
+        (Some("remove"), Some(account_id), _) if state.whitelist.remove_account(account_id) => format!("Removed account {account_id} from the whitelist."),
+        (Some("remove"), Some(account_id), _) => format!("Account {account_id} isn't whitelisted."),
+        (Some("addsteam"), _, Some(steam_id)) if state.whitelist.add_steam_id(steam_id) => format!("Whitelisted steam id {steam_id}."),
+        (Some("addsteam"), _, Some(steam_id)) => format!("Steam id {steam_id} is already whitelisted."),
+        (Some("removesteam"), _, Some(steam_id)) if state.whitelist.remove_steam_id(steam_id) => format!("Removed steam id {steam_id} from the whitelist."),
+        (Some("removesteam"), _, Some(steam_id)) => format!("Steam id {steam_id} isn't whitelisted."),
+        _ => "Give a player, account id or steam id.".to_string(),
+    };
+
+    ctx.reply(&message);
+}
+
+pub fn adminchat(ctx: &mut CommandContext, args: &ArgValues) {
+    send_admin_chat(ctx.client.as_deref(), args.text(0).unwrap_or_default(), ctx.state);
+}
+
+pub fn mute(ctx: &mut CommandContext, args: &ArgValues) {
+    let Some(account_id) = args.account(0) else {
+        return;
+    };
+
+    let minutes = args.int(1).map_or(0, |minutes| minutes.clamp(0, u32::MAX as i64) as u32);
+    let name = ctx.state.player_store.lock().unwrap().account_name(account_id);
+
+    ctx.state.chat.mute(account_id, minutes);
+
+    if minutes > 0 {
+        ctx.reply(&format!("Muted {name} for {minutes} minutes."));
+    } else {
+        ctx.reply(&format!("Muted {name}."));
+    }
+}
+
+pub fn unmute(ctx: &mut CommandContext, args: &ArgValues) {
+    let Some(account_id) = args.account(0) else {
+        return;
+    };
+
+    let name = ctx.state.player_store.lock().unwrap().account_name(account_id);
+
+    if ctx.state.chat.unmute(account_id) {
+        ctx.reply(&format!("Unmuted {name}."));
+    } else {
+        ctx.reply(&format!("{name} isn't muted."));
+    }
+}
+
+pub fn voicemute(ctx: &mut CommandContext, args: &ArgValues) {
+    let Some(account_id) = args.account(0) else {
+        return;
+    };
+
+    let name = ctx.state.player_store.lock().unwrap().account_name(account_id);
+
+    if ctx.state.voices.mute(account_id) {
+        ctx.reply(&format!("Voice muted {name}."));
+    } else {
+        ctx.reply(&format!("{name} is already voice muted."));
+    }
+}
+
+pub fn voiceunmute(ctx: &mut CommandContext, args: &ArgValues) {
+    let Some(account_id) = args.account(0) else {
+        return;
+    };
+
+    let name = ctx.state.player_store.lock().unwrap().account_name(account_id);
+
+    if ctx.state.voices.unmute(account_id) {
+        ctx.reply(&format!("Voice unmuted {name}."));
+    } else {
+        ctx.reply(&format!("{name} isn't voice muted."));
+    }
+}
@@ -0,0 +1,175 @@
+use crate::{
+    access::permissions::{login_enabled, Role},
+    chat::{send_private_message, send_team_chat},
+    commands::{args::ArgValues, CommandContext},
+    connection::{customization::parse_team_uniforms, CharacterCustomization},
+    corporations::actions,
+    packets::utils::to_ascii,
+};
+
+pub fn login(ctx: &mut CommandContext, args: &ArgValues) {
+    let state = ctx.state;
+    let password = args.text(0).unwrap_or_default();
+    let (enabled, admin_password) = {
+        let config = state.config();
+
+        (login_enabled(&config), config.admin_password.clone())
+    };
+    let client = ctx.player();
+
+    let message = if !enabled {
+        "Logging in is disabled on this server.".to_string()
+    } else if let Some(wait) = state.logins.locked_for(client.account_id) {
+        format!("Too many wrong passwords, try again in {} minutes.", wait.as_secs() / 60 + 1)
+    } else if password == admin_password {
+        audit_log!("{} ({}) logged in as an admin.", client.username, client.account_id);
+
+        state.logins.succeed(client.account_id);
+        client.role = Role::Admin;
+        "You are now logged in as an admin.".to_string()
+    } else {
+        let left = state.logins.fail(client.account_id);
+
+        audit_log!("{} ({}) failed to log in, {left} tries left.", client.username, client.account_id);
+
+        if left == 0 {
+            warn!("server", "{} ({}) is locked out of /login.", client.username, client.account_id);
+
+            state.kick(client.client_id, "Too many wrong passwords.");
+
+            return;
+        }
+
+        "Wrong password.".to_string()
+    };
+
+    ctx.reply(&message);
+}
+
+pub fn nick(ctx: &mut CommandContext, args: &ArgValues) {
+    let state = ctx.state;
+    let client = ctx.player();
+
+    // Same limits as the name field in EventUpdatePlayer, so what they see is what everyone else gets.
+    let mut name = to_ascii(args.text(0).unwrap_or_default()).trim().to_string();
+    name.truncate(31);
+
+    if name.is_empty() {
+        ctx.reply("Names need at least one character the game can show.");
+
+        return;
+    }
+
+    client.username = name;
+    client.update_player(state);
+}
+
+pub fn customize(ctx: &mut CommandContext, args: &ArgValues) {
+    let state = ctx.state;
+    let client = ctx.player();
+
+    let (Some(part), Some(value)) = (args.text(0), args.text(1)) else {
+        let message = client.customization.menu();
+        ctx.reply(&message);
+
+        return;
+    };
+
+    let Some(field) = CharacterCustomization::find_field(part) else {
+        ctx.reply(&format!("There's no part called {part}, see /customize."));
+
+        return;
+    };
+
+    let range = CharacterCustomization::allowed_range(field).unwrap_or(0..=0);
+
+    let value = match value.to_lowercase().as_str() {
+        "next" => client.customization.step(field, true),
+        "prev" => client.customization.step(field, false),
+        value => value.parse::<i32>().ok(),
+    };
+
+    let Some(value) = value.filter(|value| range.contains(value)) else {
+        ctx.reply(&format!("{field} goes from {} to {}.", range.start(), range.end()));
+
+        return;
+    };
+
+    if let Some(current) = client.customization.field_mut(field) {
+        *current = value;
+    }
+
+    let uniformed = matches!(field, "model" | "suit_color" | "tie_color")
+        && parse_team_uniforms(&state.config().team_uniforms).contains_key(&client.team);
+
+    client.update_player(state);
+
+    if uniformed {
+        ctx.reply(&format!("Set your {field} to {value}, but your team's uniform covers it for now."));
+    } else {
+        ctx.reply(&format!("Set your {field} to {value}."));
+    }
+}
+
+pub fn msg(ctx: &mut CommandContext, args: &ArgValues) {
+    let Some((client_id, name)) = args.player(0).and_then(|addr| ctx.state.connections.get(&addr).map(|conn| (conn.client_id, conn.username.clone()))) else {
+        return;
+    };
+    let message = args.text(1).unwrap_or_default();
+
+    match send_private_message(ctx.client.as_deref_mut(), (client_id, &name), message, ctx.state) {
+        Ok(sent) => ctx.reply(&format!("To {name}: {sent}")),
+        Err(reason) => ctx.reply(&reason),
+    }
+}
+
+pub fn team(ctx: &mut CommandContext, args: &ArgValues) {
+    let state = ctx.state;
+
+    if let Err(reason) = send_team_chat(ctx.player(), args.text(0).unwrap_or_default(), state) {
+        ctx.reply(&reason);
+    }
+}
+
+pub fn stocks(ctx: &mut CommandContext, _: &ArgValues) {
+    let prices = ctx.state.stocks.stocks.iter().map(|stock| format!("{:?}: ${}", stock.team, stock.price)).collect::<Vec<String>>();
+
+    ctx.reply(&prices.join(", "));
+}
+
+pub fn stats(ctx: &mut CommandContext, _: &ArgValues) {
+    let account_id = ctx.player().account_id;
+    let stats = ctx.state.player_store.lock().unwrap().get_stats(account_id, ctx.state.config().gamemode);
+
+    let message = if stats.is_empty() {
+        "No stats recorded for this mode.".to_string()
+    } else {
+        stats.iter().map(|(stat, value)| format!("{stat}: {value}")).collect::<Vec<String>>().join(", ")
+    };
+
+    ctx.reply(&message);
+}
+
+pub fn corp(ctx: &mut CommandContext, args: &ArgValues) {
+    let state = ctx.state;
+    let client = ctx.player();
+    let target = args.text(1);
+    let pick = target.and_then(|word| word.parse::<usize>().ok());
+
+    let message = match args.choice(0).unwrap_or("team") {
+        "apply" => actions::apply(state, client.account_id, target.and_then(|word| word.parse().ok())),
+        "hire" => actions::hire(state, client.account_id, pick),
+        "fire" => actions::fire(state, client.account_id, pick),
+        "requisition" => actions::requisition(state, client, pick),
+        _ => actions::team(state, client.account_id),
+    };
+
+    ctx.reply(&message);
+}
+
+pub fn quitcorp(ctx: &mut CommandContext, _: &ArgValues) {
+    let account_id = ctx.player().account_id;
+    ctx.state.corporations.quit(account_id);
+
+    ctx.reply("You quit your corporation.");
+}
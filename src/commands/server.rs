@@ -0,0 +1,61 @@
+use crate::{
+    app_state::ChatType,
+    commands::{args::ArgValues, CommandContext},
+};
+
+pub fn status(ctx: &mut CommandContext, _: &ArgValues) {
+    let state = ctx.state;
+
+    ctx.reply(&format!(
+        "{:?} on {}, {:?}, round {}, tick {}.",
+        state.config().gamemode,
+        state.map_name(),
+        state.game_state(),
+        state.round_number(),
+        state.network_tick()
+    ));
+
+    ctx.reply(&format!("{}/{} players online.", state.connections.len(), state.config().max_players));
+
+    let mut connections = state.connections.iter().map(|conn| (conn.client_id, conn.username.clone(), conn.account_id, conn.address, conn.role)).collect::<Vec<_>>();
+    connections.sort_by_key(|(client_id, ..)| *client_id);
+
+    for (client_id, name, account_id, address, role) in connections {
+        ctx.reply(&format!("  {client_id}: {name} (account {account_id}, {address}) {role}"));
+    }
+}
+
+// client id, name, account id, address, role
+pub fn players(ctx: &mut CommandContext, _: &ArgValues) {
+    let mut connections = ctx.state.connections.iter().map(|conn| (conn.client_id, conn.username.clone(), conn.account_id, conn.address, conn.role)).collect::<Vec<_>>();
+    connections.sort_by_key(|(client_id, ..)| *client_id);
+
+    for (client_id, name, account_id, address, role) in connections {
+        ctx.reply(&format!("{client_id}\t{name}\t{account_id}\t{address}\t{role}"));
+    }
+}
+
+pub fn say(ctx: &mut CommandContext, args: &ArgValues) {
+    ctx.state.send_chat(ChatType::AdminChat, &format!("Console: {}", args.text(0).unwrap_or_default()), -1, 0);
+}
+
+pub fn save(ctx: &mut CommandContext, _: &ArgValues) {
+    ctx.state.save_players();
+    ctx.state.whitelist.save();
+
+    ctx.reply("Saved.");
+}
+
+pub fn reloadconfig(ctx: &mut CommandContext, _: &ArgValues) {
+    let needs_restart = ctx.state.reload_config();
+
+    if needs_restart.is_empty() {
+        ctx.reply("Reloaded config.txt.");
+    } else {
+        ctx.reply(&format!("Reloaded config.txt, {} will change after a restart.", needs_restart.join(", ")));
+    }
+}
+
+pub fn shutdown(ctx: &mut CommandContext, _: &ArgValues) {
+    ctx.state.shutdown();
+}
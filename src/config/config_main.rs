@@ -3,23 +3,59 @@ use std::{collections::HashMap, fs::File, io::{Write, BufRead}};
 use serde::{Deserialize, Serialize};
 use serde_json::json;
 
-use crate::config::{decode_gamemode_str, get_bool_from_config, get_string_from_config, get_u32_from_config, GameMode};
+use crate::{config::{decode_gamemode_str, decode_player_store_str, get_bool_from_config, get_string_from_config, get_u32_from_config, get_u32_list_from_config, GameMode}, storage::PlayerStoreType};
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct ConfigMain {
     pub master_server_url: String,
     pub port: u16,
     pub server_name: String,
+    // For /login, which stays off while this is empty or still "admin".
     pub admin_password: String,
+    pub admins: Vec<u32>,
+    pub moderators: Vec<u32>,
     pub server_password: String,
     pub gamemode: GameMode,
     pub max_players: u8,
+    // Slots out of max_players that only reserved_accounts can take.
+    pub reserved_slots: u8,
+    pub reserved_accounts: Vec<u32>,
+    // Only let in accounts listed in whitelist.json.
+    pub whitelist: bool,
     pub round_time: u32,
+    // Relay voice to whoever is in range of the speaker.
     pub voice_chat: bool,
+    // Milliseconds a speaker keeps their slot after going quiet.
     pub voice_min: u32,
+    // Volume levels added to every relayed voice, the most that helps is 3.
     pub voice_boost: u32,
     pub help: bool,
     pub manual_hands: bool,
+    pub player_store: PlayerStoreType,
+    pub sqlite_path: String,
+    // Remote admin, off while the password is empty.
+    pub rcon_address: String,
+    pub rcon_port: u16,
+    pub rcon_password: String,
+    // JSON and Prometheus stats over HTTP, off while the port is 0.
+    pub metrics_address: String,
+    pub metrics_port: u16,
+    // error, warn, info, debug or trace. log_levels overrides it per target, like srk=debug,packets=trace.
+    pub log_level: String,
+    pub log_levels: String,
+    // Where server.log, chat.log and audit.log go, no log files while it's empty.
+    pub log_dir: String,
+    // In KB, a full log is moved to .1 and log_files old ones are kept.
+    pub log_max_size: u32,
+    pub log_files: u32,
+    // Suit and tie colors forced on a team, like goldmen=2/1,monsota=4/0.
+    pub team_uniforms: String,
+    // Comma separated words that get starred out of chat.
+    pub chat_filter: String,
+    // Messages a player can send every 10 seconds, 0 for no limit.
+    pub chat_rate_limit: u32,
+    // Only players close enough hear normal chat, how close depends on if they whisper, talk or yell.
+    pub proximity_chat: bool,
 }
 
 impl Default for ConfigMain {
@@ -28,23 +64,44 @@ impl Default for ConfigMain {
             master_server_url: "www.crypticsea.com".to_string(),
             port: 27584,
             server_name: "Baro Serv".to_string(),
-            admin_password: "admin".to_string(),
+            admin_password: "".to_string(),
+            admins: vec![],
+            moderators: vec![],
             server_password: "".to_string(),
             gamemode: GameMode::Round,
             max_players: 16,
+            reserved_slots: 0,
+            reserved_accounts: vec![],
+            whitelist: false,
             round_time: 300, // 5 minutes
             voice_chat: false,
             voice_min: 1000, // Default to 1 second
             voice_boost: 0, // No boost by default
             help: true,
             manual_hands: false,
+            player_store: PlayerStoreType::Srk,
+            sqlite_path: "players.db".to_string(),
+            rcon_address: "127.0.0.1".to_string(),
+            rcon_port: 27590,
+            rcon_password: "".to_string(),
+            metrics_address: "127.0.0.1".to_string(),
+            metrics_port: 27591,
+            log_level: "info".to_string(),
+            log_levels: "".to_string(),
+            log_dir: "logs".to_string(),
+            log_max_size: 10 * 1024,
+            log_files: 5,
+            team_uniforms: "".to_string(),
+            chat_filter: "".to_string(),
+            chat_rate_limit: 5,
+            proximity_chat: false,
         }
     }
 }
 
 impl ConfigMain {
     pub fn read_from_file() -> Self {
-        println!("[CONFIG] Attempting to load config.txt...");
+        info!("config", "Attempting to load config.txt...");
         let file = File::open("config.txt");
 
         if let Ok(file) = file {
@@ -60,24 +117,46 @@ impl ConfigMain {
             }
 
             let val = serde_json::to_value(&data).unwrap_or(json!({}));
+            let default = ConfigMain::default();
 
             let res = Self {
-                master_server_url: get_string_from_config(&val, "master_server_url"),
-                port: get_u32_from_config(&val, "port") as u16,
-                server_name: get_string_from_config(&val, "server_name"),
-                admin_password: get_string_from_config(&val, "admin_password"),
-                server_password: get_string_from_config(&val, "server_password"),
-                gamemode: decode_gamemode_str(&get_string_from_config(&val, "gamemode")),
-                max_players: get_u32_from_config(&val, "max_players") as u8,
-                round_time: get_u32_from_config(&val, "round_time"),
-                voice_chat: get_bool_from_config(&val, "voice_chat"),
-                voice_min: get_u32_from_config(&val, "voice_min"),
-                voice_boost: get_u32_from_config(&val, "voice_boost"),
-                help: get_bool_from_config(&val, "help"),
-                manual_hands: get_bool_from_config(&val, "manual_hands"),
+                master_server_url: get_string_from_config(&val, "master_server_url", &default.master_server_url),
+                port: get_u32_from_config(&val, "port", default.port as u32) as u16,
+                server_name: get_string_from_config(&val, "server_name", &default.server_name),
+                admin_password: get_string_from_config(&val, "admin_password", &default.admin_password),
+                admins: get_u32_list_from_config(&val, "admins", &default.admins),
+                moderators: get_u32_list_from_config(&val, "moderators", &default.moderators),
+                server_password: get_string_from_config(&val, "server_password", &default.server_password),
+                gamemode: decode_gamemode_str(&get_string_from_config(&val, "gamemode", "")),
+                max_players: get_u32_from_config(&val, "max_players", default.max_players as u32) as u8,
+                reserved_slots: get_u32_from_config(&val, "reserved_slots", default.reserved_slots as u32) as u8,
+                reserved_accounts: get_u32_list_from_config(&val, "reserved_accounts", &default.reserved_accounts),
+                whitelist: get_bool_from_config(&val, "whitelist", default.whitelist),
+                round_time: get_u32_from_config(&val, "round_time", default.round_time),
+                voice_chat: get_bool_from_config(&val, "voice_chat", default.voice_chat),
+                voice_min: get_u32_from_config(&val, "voice_min", default.voice_min),
+                voice_boost: get_u32_from_config(&val, "voice_boost", default.voice_boost),
+                help: get_bool_from_config(&val, "help", default.help),
+                manual_hands: get_bool_from_config(&val, "manual_hands", default.manual_hands),
+                player_store: decode_player_store_str(&get_string_from_config(&val, "player_store", "")),
+                sqlite_path: Some(get_string_from_config(&val, "sqlite_path", &default.sqlite_path)).filter(|path| !path.is_empty()).unwrap_or(default.sqlite_path.clone()),
+                rcon_address: Some(get_string_from_config(&val, "rcon_address", &default.rcon_address)).filter(|address| !address.is_empty()).unwrap_or(default.rcon_address.clone()),
+                rcon_port: get_u32_from_config(&val, "rcon_port", default.rcon_port as u32) as u16,
+                rcon_password: get_string_from_config(&val, "rcon_password", &default.rcon_password),
+                metrics_address: Some(get_string_from_config(&val, "metrics_address", &default.metrics_address)).filter(|address| !address.is_empty()).unwrap_or(default.metrics_address.clone()),
+                metrics_port: get_u32_from_config(&val, "metrics_port", default.metrics_port as u32) as u16,
+                log_level: Some(get_string_from_config(&val, "log_level", &default.log_level)).filter(|level| !level.is_empty()).unwrap_or(default.log_level.clone()),
+                log_levels: get_string_from_config(&val, "log_levels", &default.log_levels),
+                log_dir: get_string_from_config(&val, "log_dir", &default.log_dir),
+                log_max_size: get_u32_from_config(&val, "log_max_size", default.log_max_size),
+                log_files: get_u32_from_config(&val, "log_files", default.log_files),
+                team_uniforms: get_string_from_config(&val, "team_uniforms", &default.team_uniforms),
+                chat_filter: get_string_from_config(&val, "chat_filter", &default.chat_filter),
+                chat_rate_limit: get_u32_from_config(&val, "chat_rate_limit", default.chat_rate_limit),
+                proximity_chat: get_bool_from_config(&val, "proximity_chat", default.proximity_chat),
             };
 
-            println!("[CONFIG] Successfully loaded config.txt!");
+            info!("config", "Successfully loaded config.txt!");
             res
         } else {
             let config = ConfigMain::default();
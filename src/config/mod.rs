@@ -1,7 +1,7 @@
 
 use serde_json::Value;
 
-use crate::packets::GameMode;
+use crate::{packets::GameMode, storage::PlayerStoreType};
 pub mod config_main;
 
 pub fn decode_gamemode_str(mode: &str) -> GameMode {
@@ -14,6 +14,13 @@ pub fn decode_gamemode_str(mode: &str) -> GameMode {
     }
 }
 
+pub fn decode_player_store_str(store: &str) -> PlayerStoreType {
+    match store.to_lowercase().as_str() {
+        "sqlite" => PlayerStoreType::Sqlite,
+        _ => PlayerStoreType::Srk, // Default to the SRK file if unknown
+    }
+}
+
 pub fn init_config_dirs() {
     let folder = format!("{}/Sub Rosa", dirs_next::document_dir().unwrap().to_str().unwrap());
 
@@ -22,23 +29,33 @@ pub fn init_config_dirs() {
     }
 }
 
-pub fn get_bool_from_config(config: &Value, key: &str) -> bool {
-    let value = config.get(key).and_then(Value::as_bool);
-    value.unwrap_or(false)
+// Missing or unreadable keys fall back to the default, so an older config.txt keeps working.
+pub fn get_bool_from_config(config: &Value, key: &str, default: bool) -> bool {
+    // Everything in config.txt is read in as a string.
+    let value = config.get(key).and_then(Value::as_str).and_then(|v| v.parse::<bool>().ok());
+    value.unwrap_or(default)
 }
 
-pub fn get_u8_from_config(config: &Value, key: &str) -> u8 {
-    let value = config.get(key).and_then(Value::as_str).unwrap().parse::<u8>().ok();
-    value.map_or(0, |v| v)
+pub fn get_u8_from_config(config: &Value, key: &str, default: u8) -> u8 {
+    let value = config.get(key).and_then(Value::as_str).and_then(|v| v.parse::<u8>().ok());
+    value.unwrap_or(default)
 }
 
-pub fn get_u32_from_config(config: &Value, key: &str) -> u32 {
-    let value = config.get(key).and_then(Value::as_str).unwrap().parse::<u32>().ok();
-    value.map_or(0, |v| v)
+pub fn get_u32_from_config(config: &Value, key: &str, default: u32) -> u32 {
+    let value = config.get(key).and_then(Value::as_str).and_then(|v| v.parse::<u32>().ok());
+    value.unwrap_or(default)
 }
 
-pub fn get_string_from_config(config: &Value, key: &str) -> String {
-    let value = config.get(key).and_then(Value::as_str);
-    value.map_or(String::new(), |v| v.to_string())
+// Comma separated, the brackets are there when the list was written back out by `ConfigMain::save`.
+pub fn get_u32_list_from_config(config: &Value, key: &str, default: &[u32]) -> Vec<u32> {
+    let Some(value) = config.get(key).and_then(Value::as_str) else {
+        return default.to_vec();
+    };
+
+    value.trim_matches(['[', ']']).split(',').filter_map(|v| v.trim().parse::<u32>().ok()).collect()
 }
 
+pub fn get_string_from_config(config: &Value, key: &str, default: &str) -> String {
+    let value = config.get(key).and_then(Value::as_str);
+    value.unwrap_or(default).to_string()
+}
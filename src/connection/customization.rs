@@ -0,0 +1,367 @@
+use std::{collections::HashMap, ops::RangeInclusive};
+
+use crate::packets::Team;
+
+// Set in the first saved word once an account has chosen its look, so an all zero look still loads.
+const SAVED_FLAG: u32 = 1 << 31;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CharacterCustomization {
+    pub gender: i32,
+    pub head: i32,
+    pub skin: i32,
+    pub hair_color: i32,
+    pub hair_style: i32,
+    pub eye_color: i32,
+    pub model: i32,
+    pub necklace: i32,
+    pub suit_color: i32,
+    pub tie_color: i32,
+}
+
+impl Default for CharacterCustomization {
+    fn default() -> Self {
+        Self {
+            gender: 0,
+            head: 4,
+            skin: 2,
+            hair_color: 4,
+            hair_style: 6,
+            eye_color: 6,
+            model: 1,
+            necklace: 0,
+            suit_color: 0,
+            tie_color: 1,
+        }
+    }
+}
+
+// Each field is masked to its width so one out of range value can't spill into the next.
+fn pack_bits(fields: &[(i32, u32)]) -> u32 {
+    let mut word = 0;
+    let mut shift = 0;
+
+    for (value, bits) in fields {
+        word |= (*value as u32 & ((1 << bits) - 1)) << shift;
+        shift += bits;
+    }
+
+    word
+}
+
+fn unpack_bits<const N: usize>(word: u32, widths: [u32; N]) -> [i32; N] {
+    let mut shift = 0;
+
+    widths.map(|bits| {
+        let value = (word >> shift) & ((1 << bits) - 1);
+        shift += bits;
+
+        value as i32
+    })
+}
+
+impl CharacterCustomization {
+    // What /customize takes.
+    pub const FIELDS: [&'static str; 10] =
+        ["gender", "head", "skin", "hair_style", "eye_color", "hair_color", "model", "suit_color", "tie_color", "necklace"];
+
+    // What a field can be set to. Only gender and model are known to stop early, the rest go as far as
+    // the bits the client reads for them.
+    pub fn allowed_range(field: &str) -> Option<RangeInclusive<i32>> {
+        let bits = match field {
+            "gender" | "model" => return Some(0..=1),
+            "skin" | "eye_color" => 3,
+            "hair_color" | "suit_color" | "tie_color" | "necklace" => 4,
+            "head" | "hair_style" => 5,
+            _ => return None,
+        };
+
+        Some(0..=(1 << bits) - 1)
+    }
+
+    // Anything out of range goes back to the default, for looks loaded from storage.
+    pub fn validated(mut self) -> Self {
+        let default = Self::default();
+
+        for field in Self::FIELDS {
+            let allowed = Self::allowed_range(field).is_some_and(|range| self.get(field).is_some_and(|value| range.contains(&value)));
+
+            if !allowed && let (Some(value), Some(fallback)) = (self.field_mut(field), default.get(field)) {
+                *value = fallback;
+            }
+        }
+
+        self
+    }
+
+    // What the client is sent, the team's uniform over whatever they picked.
+    pub fn wearing(&self, uniform: Option<&Uniform>) -> Self {
+        let mut worn = self.clone();
+
+        if let Some(uniform) = uniform {
+            worn.model = 1;
+            worn.suit_color = uniform.suit_color;
+            worn.tie_color = uniform.tie_color;
+        }
+
+        worn
+    }
+
+    pub fn field_mut(&mut self, field: &str) -> Option<&mut i32> {
+        match field {
+            "gender" => Some(&mut self.gender),
+            "head" => Some(&mut self.head),
+            "skin" => Some(&mut self.skin),
+            "hair_style" => Some(&mut self.hair_style),
+            "eye_color" => Some(&mut self.eye_color),
+            "hair_color" => Some(&mut self.hair_color),
+            "model" => Some(&mut self.model),
+            "suit_color" => Some(&mut self.suit_color),
+            "tie_color" => Some(&mut self.tie_color),
+            "necklace" => Some(&mut self.necklace),
+            _ => None,
+        }
+    }
+
+    pub fn get(&self, field: &str) -> Option<i32> {
+        match field {
+            "gender" => Some(self.gender),
+            "head" => Some(self.head),
+            "skin" => Some(self.skin),
+            "hair_style" => Some(self.hair_style),
+            "eye_color" => Some(self.eye_color),
+            "hair_color" => Some(self.hair_color),
+            "model" => Some(self.model),
+            "suit_color" => Some(self.suit_color),
+            "tie_color" => Some(self.tie_color),
+            "necklace" => Some(self.necklace),
+            _ => None,
+        }
+    }
+
+    // The /customize menu. No client menu id is known for customizing, so it's numbered in chat like the corporation
+    // menus.
+    pub fn menu(&self) -> String {
+        let parts = Self::FIELDS.iter().enumerate().map(|(i, field)| {
+            let range = Self::allowed_range(field).unwrap_or(0..=0);
+
+            format!("{}. {field} {} ({}-{})", i + 1, self.get(field).unwrap_or(0), range.start(), range.end())
+        });
+
+        format!("{}. Change one with /customize <part> <value|next|prev>.", parts.collect::<Vec<String>>().join(", "))
+    }
+
+    // A part by its name or its number in the menu.
+    pub fn find_field(part: &str) -> Option<&'static str> {
+        match part.parse::<usize>() {
+            Ok(number) => Self::FIELDS.get(number.checked_sub(1)?).copied(),
+            Err(_) => Self::FIELDS.iter().find(|field| field.eq_ignore_ascii_case(part)).copied(),
+        }
+    }
+
+    // Next or previous value of a field, wrapping around at either end.
+    pub fn step(&self, field: &str, forward: bool) -> Option<i32> {
+        let range = Self::allowed_range(field)?;
+        let len = range.end() - range.start() + 1;
+        let offset = self.get(field)? - range.start() + if forward { 1 } else { -1 };
+
+        Some(range.start() + offset.rem_euclid(len))
+    }
+
+    // Body and face, sent as a whole u32 in EventUpdatePlayer. Neither layout has been checked against a capture of a
+    // real client yet, only that they round-trip.
+    pub fn pack_body(&self) -> u32 {
+        pack_bits(&[(self.gender, 1), (self.head, 5), (self.skin, 3), (self.hair_style, 5)])
+    }
+
+    // Colors and clothes, the 24 bits after it.
+    pub fn pack_clothes(&self) -> u32 {
+        pack_bits(&[
+            (self.eye_color, 3),
+            (self.hair_color, 4),
+            (self.model, 5),
+            (self.suit_color, 4),
+            (self.tie_color, 4),
+            (self.necklace, 4),
+        ])
+    }
+
+    pub fn unpack(body: u32, clothes: u32) -> Self {
+        let [gender, head, skin, hair_style] = unpack_bits(body, [1, 5, 3, 5]);
+        let [eye_color, hair_color, model, suit_color, tie_color, necklace] = unpack_bits(clothes, [3, 4, 5, 4, 4, 4]);
+
+        Self {
+            gender,
+            head,
+            skin,
+            hair_color,
+            hair_style,
+            eye_color,
+            model,
+            necklace,
+            suit_color,
+            tie_color,
+        }
+    }
+
+    // The two words kept in the player store.
+    pub fn to_saved(&self) -> (u32, u32) {
+        (self.pack_body() | SAVED_FLAG, self.pack_clothes())
+    }
+
+    // None if the account never picked a look.
+    pub fn from_saved(body: u32, clothes: u32) -> Option<Self> {
+        (body & SAVED_FLAG != 0).then(|| Self::unpack(body, clothes).validated())
+    }
+}
+
+// Uniforms are suits, so they also put everyone in the suit model.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Uniform {
+    pub suit_color: i32,
+    pub tie_color: i32,
+}
+
+// "goldmen=2/1,monsota=4/0" into suit/tie colors per team, anything that doesn't parse or is out of range is skipped.
+pub fn parse_team_uniforms(uniforms: &str) -> HashMap<Team, Uniform> {
+    let suit_range = CharacterCustomization::allowed_range("suit_color").unwrap_or(0..=0);
+    let tie_range = CharacterCustomization::allowed_range("tie_color").unwrap_or(0..=0);
+
+    uniforms
+        .split(',')
+        .filter_map(|entry| {
+            let (team, colors) = entry.split_once('=')?;
+            let (suit, tie) = colors.split_once('/')?;
+
+            let uniform = Uniform {
+                suit_color: suit.trim().parse().ok()?,
+                tie_color: tie.trim().parse().ok()?,
+            };
+
+            (suit_range.contains(&uniform.suit_color) && tie_range.contains(&uniform.tie_color)).then_some((team.parse().ok()?, uniform))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Every field at the top of its range, so a wrong width or shift shows up.
+    fn maxed() -> CharacterCustomization {
+        let mut look = CharacterCustomization::default();
+
+        for field in CharacterCustomization::FIELDS {
+            let max = *CharacterCustomization::allowed_range(field).unwrap().end();
+            *look.field_mut(field).unwrap() = max;
+        }
+
+        look
+    }
+
+    #[test]
+    fn packing_round_trips() {
+        for look in [CharacterCustomization::default(), maxed()] {
+            assert_eq!(CharacterCustomization::unpack(look.pack_body(), look.pack_clothes()), look);
+        }
+    }
+
+    #[test]
+    fn packed_words_stay_in_their_widths() {
+        let look = maxed();
+
+        assert!(look.pack_body() < 1 << 14);
+        assert!(look.pack_clothes() < 1 << 24);
+    }
+
+    #[test]
+    fn each_field_lands_in_its_own_bits() {
+        let look = CharacterCustomization {
+            gender: 1,
+            head: 2,
+            skin: 3,
+            hair_style: 4,
+            eye_color: 5,
+            hair_color: 6,
+            model: 1,
+            suit_color: 7,
+            tie_color: 8,
+            necklace: 9,
+        };
+
+        assert_eq!(look.pack_body(), 1 | 2 << 1 | 3 << 6 | 4 << 9);
+        assert_eq!(look.pack_clothes(), 5 | 6 << 3 | 1 << 7 | 7 << 12 | 8 << 16 | 9 << 20);
+    }
+
+    #[test]
+    fn out_of_range_values_cant_spill_into_the_next_field() {
+        let look = CharacterCustomization {
+            gender: 3,
+            head: -1,
+            ..CharacterCustomization::default()
+        };
+
+        let unpacked = CharacterCustomization::unpack(look.pack_body(), look.pack_clothes());
+
+        assert_eq!(unpacked.gender, 1);
+        assert_eq!(unpacked.head, 31);
+        assert_eq!(unpacked.skin, look.skin);
+    }
+
+    #[test]
+    fn saved_looks_round_trip() {
+        let look = maxed();
+        let (body, clothes) = look.to_saved();
+
+        assert_eq!(CharacterCustomization::from_saved(body, clothes), Some(look));
+    }
+
+    #[test]
+    fn accounts_that_never_picked_a_look_load_none() {
+        assert_eq!(CharacterCustomization::from_saved(0, 0), None);
+
+        // All zeros is still a look once it's been saved.
+        let zeroed = CharacterCustomization::unpack(0, 0);
+        let (body, clothes) = zeroed.to_saved();
+        assert_eq!(CharacterCustomization::from_saved(body, clothes), Some(zeroed));
+    }
+
+    #[test]
+    fn validated_resets_only_bad_fields() {
+        let look = CharacterCustomization {
+            gender: 2,
+            model: -1,
+            head: 20,
+            ..CharacterCustomization::default()
+        }
+        .validated();
+
+        let default = CharacterCustomization::default();
+        assert_eq!(look.gender, default.gender);
+        assert_eq!(look.model, default.model);
+        assert_eq!(look.head, 20);
+    }
+
+    #[test]
+    fn menu_parts_and_stepping() {
+        assert_eq!(CharacterCustomization::find_field("1"), Some("gender"));
+        assert_eq!(CharacterCustomization::find_field("Necklace"), Some("necklace"));
+        assert_eq!(CharacterCustomization::find_field("0"), None);
+        assert_eq!(CharacterCustomization::find_field("11"), None);
+
+        let look = maxed();
+        assert_eq!(look.step("head", true), Some(0));
+        assert_eq!(look.step("head", false), Some(30));
+        assert_eq!(CharacterCustomization::default().step("gender", false), Some(1));
+        assert_eq!(look.step("hat", true), None);
+    }
+
+    #[test]
+    fn uniforms_parse_and_skip_bad_entries() {
+        let uniforms = parse_team_uniforms("goldmen=2/1, monsota = 4/0, oxs=99/1, nexaco=1, nobody=1/1");
+
+        assert_eq!(uniforms.len(), 2);
+        assert_eq!(uniforms.get(&Team::Goldmen), Some(&Uniform { suit_color: 2, tie_color: 1 }));
+        assert_eq!(uniforms.get(&Team::Monsota), Some(&Uniform { suit_color: 4, tie_color: 0 }));
+    }
+}
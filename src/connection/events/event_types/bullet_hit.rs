@@ -10,7 +10,7 @@ pub struct EventBulletHit {
 }
 
 impl WriterEncodable for EventBulletHit {
-    fn encode(&self, _state: &crate::AppState, writer: &mut AlexBufWriter) {
+    fn encode(&self, writer: &mut AlexBufWriter) {
         writer.write_bits(1, 6);
         writer.write_bits(self.tick_created, 28);
         writer.write_bits(self.unk, 4);
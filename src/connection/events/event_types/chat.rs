@@ -1,4 +1,7 @@
-use crate::{app_state::ChatType, packets::{buf_writer::AlexBufWriter, WriterEncodable}};
+use crate::{app_state::ChatType, packets::{buf_writer::AlexBufWriter, utils::to_ascii, WriterEncodable}};
+
+// The length is 6 bits and counts the terminator.
+pub const MAX_CHAT_LENGTH: usize = 62;
 
 #[derive(Clone, Debug)]
 pub struct EventChat {
@@ -10,11 +13,13 @@ pub struct EventChat {
 }
 
 impl WriterEncodable for EventChat {
-    fn encode(&self, _state: &crate::AppState, writer: &mut AlexBufWriter) {
+    fn encode(&self, writer: &mut AlexBufWriter) {
         writer.write_bits(2, 6);
         writer.write_bits(self.tick_created, 28);
 
-        let mut message = self.message.clone();
+        // AppState::send_chat already splits and cleans messages, this just makes sure nothing else can corrupt the packet.
+        let mut message = to_ascii(&self.message);
+        message.truncate(MAX_CHAT_LENGTH);
         message.push('\0');
 
         writer.write_bits(message.len() as i32, 6);
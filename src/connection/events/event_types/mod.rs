@@ -10,6 +10,11 @@ pub mod update_player_round;
 pub mod chat;
 pub mod sound;
 
+// Event ids are the first 6 bits of every event. Known so far:
+//  1 BulletHit, 2 Chat, 3 UpdateVehicleTypeColor, 4 UpdateVehicle, 6 UpdatePhone,
+//  7 UpdatePlayer, 8 UpdatePlayerRound, 9 Sound, 10 TeamDoorState.
+// 5 and 11 up are left out on purpose. Nobody has their layouts, and a wrong one throws off every bit read after it in
+// the packet, so they stay out until someone has captures from a real server to work from.
 #[derive(Clone, Debug)]
 pub enum Event {
     BulletHit(EventBulletHit),
@@ -24,17 +29,273 @@ pub enum Event {
 }
 
 impl WriterEncodable for Event {
-    fn encode(&self, state: &crate::AppState, writer: &mut AlexBufWriter) {
+    fn encode(&self, writer: &mut AlexBufWriter) {
         match self {
-            Event::BulletHit(event_bullet_hit) => event_bullet_hit.encode(state, writer),
-            Event::UpdateVehicleTypeColor(event_update_vehicle_type_color) => event_update_vehicle_type_color.encode(state, writer),
-            Event::UpdateVehicle(event_update_vehicle) => event_update_vehicle.encode(state, writer),
-            Event::UpdatePhone(event_update_phone) => event_update_phone.encode(state, writer),
-            Event::UpdatePlayer(event_update_player) => event_update_player.encode(state, writer),
-            Event::UpdatePlayerRound(event_update_player_round) => event_update_player_round.encode(state, writer),
-            Event::TeamDoorState(event_team_door_state) => event_team_door_state.encode(state, writer),
-            Event::Chat(event_chat) => event_chat.encode(state, writer),
-            Event::Sound(event_sound) => event_sound.encode(state, writer),
+            Event::BulletHit(event_bullet_hit) => event_bullet_hit.encode(writer),
+            Event::UpdateVehicleTypeColor(event_update_vehicle_type_color) => event_update_vehicle_type_color.encode(writer),
+            Event::UpdateVehicle(event_update_vehicle) => event_update_vehicle.encode(writer),
+            Event::UpdatePhone(event_update_phone) => event_update_phone.encode(writer),
+            Event::UpdatePlayer(event_update_player) => event_update_player.encode(writer),
+            Event::UpdatePlayerRound(event_update_player_round) => event_update_player_round.encode(writer),
+            Event::TeamDoorState(event_team_door_state) => event_team_door_state.encode(writer),
+            Event::Chat(event_chat) => event_chat.encode(writer),
+            Event::Sound(event_sound) => event_sound.encode(writer),
         }
     }
-}
\ No newline at end of file
+}
+
+// Each event written out and read back field by field. This only pins down what we send, so a change to it can't slip
+// through unnoticed. Whether the client reads it the same way still needs a capture to tell.
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        app_state::ChatType,
+        connection::{
+            events::event_types::sound::SoundType,
+            packets::buf_reader::AlexBufReader,
+            CharacterCustomization,
+        },
+        packets::Team,
+        world::vector::Vector,
+    };
+
+    // Uses all 28 bits.
+    const TICK: i32 = 0x0abc_def1;
+
+    fn vector(x: f32, y: f32, z: f32) -> Vector {
+        Vector { x, y, z }
+    }
+
+    fn read_vector(reader: &mut AlexBufReader) -> Vector {
+        vector(reader.read_f32().unwrap(), reader.read_f32().unwrap(), reader.read_f32().unwrap())
+    }
+
+    // Hands the encoded event to `read`, then checks it read every bit that was written.
+    fn read_back(event: Event, id: u32, read: impl FnOnce(&mut AlexBufReader)) {
+        let mut writer = AlexBufWriter::new();
+        event.encode(&mut writer);
+
+        let bits = writer.bit_len();
+        let mut reader = AlexBufReader::from_buf(writer.into_vec());
+
+        assert_eq!(reader.read_bits(6), Some(id));
+        assert_eq!(reader.read_bits(28), Some(TICK as u32));
+
+        read(&mut reader);
+
+        assert_eq!(reader.pos * 8 + reader.bit_pos, bits);
+    }
+
+    #[test]
+    fn bullet_hit() {
+        let event = Event::BulletHit(EventBulletHit {
+            tick_created: TICK,
+            pos: vector(1.0, 2.0, 3.0),
+            normal: vector(0.0, 1.0, 0.0),
+            hit_type: 45,
+            unk: 9,
+        });
+
+        read_back(event, 1, |reader| {
+            assert_eq!(reader.read_bits(4), Some(9));
+            assert_eq!(reader.read_bits(6), Some(45));
+            assert_eq!(read_vector(reader), vector(1.0, 2.0, 3.0));
+            assert_eq!(read_vector(reader), vector(0.0, 1.0, 0.0));
+        });
+    }
+
+    #[test]
+    fn chat() {
+        let event = Event::Chat(EventChat {
+            tick_created: TICK,
+            chat_type: ChatType::PrivateMessage,
+            message: "Héllo".to_string(),
+            speaker_id: 700,
+            volume: 2,
+        });
+
+        read_back(event, 2, |reader| {
+            // Counts the terminator.
+            assert_eq!(reader.read_bits(6), Some(6));
+            assert_eq!(reader.read_bits(4), Some(ChatType::PrivateMessage as u32));
+            assert_eq!(reader.read_bits(10), Some(700));
+            assert_eq!(reader.read_bits(4), Some(2));
+
+            let text = (0..6).map(|_| reader.read_bits(7).unwrap() as u8).collect::<Vec<u8>>();
+            assert_eq!(text, b"Hello\0");
+        });
+    }
+
+    #[test]
+    fn chat_is_cut_to_fit_its_length_field() {
+        let event = Event::Chat(EventChat {
+            tick_created: TICK,
+            chat_type: ChatType::Announce,
+            message: "a".repeat(100),
+            speaker_id: -1,
+            volume: 0,
+        });
+
+        read_back(event, 2, |reader| {
+            assert_eq!(reader.read_bits(6), Some(63));
+            assert_eq!(reader.read_bits(4), Some(0));
+            assert_eq!(reader.read_bits(10), Some(0x3ff));
+            assert_eq!(reader.read_bits(4), Some(0));
+
+            for _ in 0..62 {
+                assert_eq!(reader.read_bits(7), Some(b'a' as u32));
+            }
+            assert_eq!(reader.read_bits(7), Some(0));
+        });
+    }
+
+    #[test]
+    fn update_vehicle_type_color() {
+        let event = Event::UpdateVehicleTypeColor(EventUpdateVehicleTypeColor {
+            tick_created: TICK,
+            vehicle_id: 513,
+            vehicle_type: 200,
+            vehicle_color: 11,
+        });
+
+        read_back(event, 3, |reader| {
+            assert_eq!(reader.read_bits(10), Some(513));
+            assert_eq!(reader.read_bits(8), Some(200));
+            assert_eq!(reader.read_bits(4), Some(11));
+        });
+    }
+
+    #[test]
+    fn update_vehicle() {
+        let event = Event::UpdateVehicle(EventUpdateVehicle {
+            tick_created: TICK,
+            vehicle_id: 1000,
+            vehicle_type: 13,
+            color: 777,
+            pos: vector(1800.0, 82.0, 1500.0),
+            velocity: vector(-1.5, 0.0, 2.25),
+        });
+
+        read_back(event, 4, |reader| {
+            assert_eq!(reader.read_bits(10), Some(1000));
+            assert_eq!(reader.read_bits(4), Some(13));
+            assert_eq!(reader.read_bits(10), Some(777));
+            assert_eq!(read_vector(reader), vector(1800.0, 82.0, 1500.0));
+            assert_eq!(read_vector(reader), vector(-1.5, 0.0, 2.25));
+        });
+    }
+
+    #[test]
+    fn update_phone() {
+        let event = Event::UpdatePhone(EventUpdatePhone {
+            tick_created: TICK,
+            item_id: 900,
+            phone_status: 5,
+            display_phone_number: 1023,
+            phone_texture: 3,
+        });
+
+        read_back(event, 6, |reader| {
+            assert_eq!(reader.read_bits(10), Some(900));
+            assert_eq!(reader.read_bits(3), Some(5));
+            assert_eq!(reader.read_bits(10), Some(1023));
+            assert_eq!(reader.read_bits(2), Some(3));
+        });
+    }
+
+    #[test]
+    fn update_player() {
+        let customization = CharacterCustomization {
+            gender: 1,
+            head: 17,
+            skin: 5,
+            hair_color: 9,
+            hair_style: 30,
+            eye_color: 6,
+            model: 1,
+            necklace: 15,
+            suit_color: 3,
+            tie_color: 12,
+        };
+
+        let event = Event::UpdatePlayer(EventUpdatePlayer {
+            tick_created: TICK,
+            client_id: 31,
+            team: Team::Pentacom,
+            customization: customization.clone(),
+            active: true,
+            is_bot: false,
+            human_id: -1,
+            name: "Zoë".to_string(),
+        });
+
+        read_back(event, 7, |reader| {
+            let a = reader.read_bits(16).unwrap();
+            assert_eq!(a & 0xff, 31);
+            assert_eq!(a >> 8 & 1, 0);
+            assert_eq!(a >> 9 & 1, 1);
+            assert_eq!(a >> 10, Team::Pentacom as u32 + 1);
+
+            assert_eq!(reader.read_bits(10), Some(0x3ff));
+
+            let body = reader.read_u32().unwrap();
+            let clothes = reader.read_bits(24).unwrap();
+            assert_eq!(CharacterCustomization::unpack(body, clothes), customization);
+
+            let name = (0..31).map(|_| reader.read_bits(7).unwrap() as u8).collect::<Vec<u8>>();
+            assert_eq!(&name[..4], b"Zoe\0");
+            assert!(name[4..].iter().all(|char| *char == 0));
+        });
+    }
+
+    #[test]
+    fn update_player_round() {
+        let event = Event::UpdatePlayerRound(EventUpdatePlayerRound {
+            tick_created: TICK,
+            client_id: 200,
+            money: -350,
+            stocks: 12,
+            phone_number: 5551234,
+        });
+
+        read_back(event, 8, |reader| {
+            assert_eq!(reader.read_bits(8), Some(200));
+            assert_eq!(reader.read_u32().map(|money| money as i32), Some(-350));
+            assert_eq!(reader.read_u32(), Some(12));
+            assert_eq!(reader.read_u32(), Some(5551234));
+        });
+    }
+
+    #[test]
+    fn sound() {
+        let event = Event::Sound(EventSound {
+            tick_created: TICK,
+            sound_type: SoundType::PhoneRing,
+            pos: vector(10.0, 20.0, 30.0),
+            volume: 0.5,
+            pitch: 1.25,
+        });
+
+        read_back(event, 9, |reader| {
+            assert_eq!(reader.read_bits(8), Some(SoundType::PhoneRing as u32));
+            assert_eq!(read_vector(reader), vector(10.0, 20.0, 30.0));
+            assert_eq!(reader.read_f32(), Some(0.5));
+            assert_eq!(reader.read_f32(), Some(1.25));
+        });
+    }
+
+    #[test]
+    fn team_door_state() {
+        let event = Event::TeamDoorState(EventTeamDoorState {
+            tick_created: TICK,
+            team_id: 4,
+            door_open: true,
+        });
+
+        read_back(event, 10, |reader| {
+            assert_eq!(reader.read_bits(8), Some(4));
+            assert_eq!(reader.read_bits(1), Some(1));
+        });
+    }
+}
@@ -52,7 +52,7 @@ pub struct EventSound {
 }
 
 impl WriterEncodable for EventSound {
-    fn encode(&self, _state: &crate::AppState, writer: &mut AlexBufWriter) {
+    fn encode(&self, writer: &mut AlexBufWriter) {
         writer.write_bits(9, 6);
         writer.write_bits(self.tick_created, 28);
 
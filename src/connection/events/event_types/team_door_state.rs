@@ -8,7 +8,7 @@ pub struct EventTeamDoorState {
 }
 
 impl WriterEncodable for EventTeamDoorState {
-    fn encode(&self, _state: &crate::AppState, writer: &mut AlexBufWriter) {
+    fn encode(&self, writer: &mut AlexBufWriter) {
         writer.write_bits(10, 6);
         writer.write_bits(self.tick_created, 28);
         writer.write_bits(self.team_id, 8);
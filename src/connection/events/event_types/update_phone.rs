@@ -10,7 +10,7 @@ pub struct EventUpdatePhone {
 }
 
 impl WriterEncodable for EventUpdatePhone {
-    fn encode(&self, _state: &crate::AppState, writer: &mut AlexBufWriter) {
+    fn encode(&self, writer: &mut AlexBufWriter) {
         writer.write_bits(6, 6);
         writer.write_bits(self.tick_created, 28);
 
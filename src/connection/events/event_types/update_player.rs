@@ -1,4 +1,4 @@
-use crate::{connection::CharacterCustomization, packets::{buf_writer::AlexBufWriter, utils::limited_string, WriterEncodable, Team}};
+use crate::{connection::CharacterCustomization, packets::{buf_writer::AlexBufWriter, utils::{limited_string, to_ascii}, WriterEncodable, Team}};
 
 #[derive(Clone, Debug)]
 pub struct EventUpdatePlayer {
@@ -12,47 +12,23 @@ pub struct EventUpdatePlayer {
     pub name: String
 }
 
-// TODO: Make these bitfields actually work
 impl WriterEncodable for EventUpdatePlayer {
-    fn encode(&self, _state: &crate::AppState, writer: &mut AlexBufWriter) {
+    fn encode(&self, writer: &mut AlexBufWriter) {
         writer.write_bits(7, 6);
         writer.write_bits(self.tick_created, 28);
 
-        // Start packing for A
-        let team_bits = (self.team as i32 + 1) << 10;
-        let active_bits = (self.active as i32) << 9;
-        let bot_bits = (self.is_bot as i32) << 8;
-
-        let a = team_bits + active_bits + self.client_id as i32 + bot_bits;
-        // -----------
-
-        // Start packing for C
-        let gender_bits = self.customization.gender;
-        let head_bits = self.customization.head << 1;
-        let skin_color_bits = self.customization.skin << 6;
-        let hair_bits = self.customization.hair_style << 9;
-
-        let c = gender_bits + head_bits + skin_color_bits + hair_bits;
-        // -----------
-
-        // Start packing for D
-        let eye_color_bits = self.customization.eye_color;
-        let hair_color_bits = self.customization.hair_color << 3;
-        let model_bits = self.customization.model << 7;
-        let suit_color_bits = self.customization.suit_color << 0xc;
-        let tie_color_bits = self.customization.tie_color << 0x10;
-        let necklace_bits = self.customization.necklace << 0x14;
-
-        let d1 = eye_color_bits + hair_color_bits + model_bits + suit_color_bits + tie_color_bits;
-        let d = necklace_bits + d1;
-        // -----------
+        // Client id, bot, active and team + 1, each masked so they can't run into each other.
+        let a = (self.client_id as i32 & 0xff) | (self.is_bot as i32) << 8 | (self.active as i32) << 9 | ((self.team as i32 + 1) & 0x3f) << 10;
+        let c = self.customization.pack_body() as i32;
+        let d = self.customization.pack_clothes() as i32;
 
         writer.write_bits(a, 16);
         writer.write_bits(self.human_id, 10);
+        // Byte aligned like the other whole ints in events, not checked against a capture yet.
         writer.write_bytes(&c.to_le_bytes());
         writer.write_bits(d, 24);
 
-        for char in limited_string(&self.name, 31) {
+        for char in limited_string(&to_ascii(&self.name), 31) {
             writer.write_bits(char as i32, 7);
         }
     }
@@ -10,7 +10,7 @@ pub struct EventUpdatePlayerRound {
 }
 
 impl WriterEncodable for EventUpdatePlayerRound {
-    fn encode(&self, _state: &crate::AppState, writer: &mut AlexBufWriter) {
+    fn encode(&self, writer: &mut AlexBufWriter) {
         writer.write_bits(8, 6);
         writer.write_bits(self.tick_created, 28);
         writer.write_bits(self.client_id as i32, 8);
@@ -11,7 +11,7 @@ pub struct EventUpdateVehicle {
 }
 
 impl WriterEncodable for EventUpdateVehicle {
-    fn encode(&self, _state: &crate::AppState, writer: &mut AlexBufWriter) {
+    fn encode(&self, writer: &mut AlexBufWriter) {
         writer.write_bits(4, 6);
         writer.write_bits(self.tick_created, 28);
         writer.write_bits(self.vehicle_id, 10);
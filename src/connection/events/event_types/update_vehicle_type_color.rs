@@ -9,7 +9,7 @@ pub struct EventUpdateVehicleTypeColor {
 }
 
 impl WriterEncodable for EventUpdateVehicleTypeColor {
-    fn encode(&self, _state: &crate::AppState, writer: &mut AlexBufWriter) {
+    fn encode(&self, writer: &mut AlexBufWriter) {
         writer.write_bits(3, 6);
         writer.write_bits(self.tick_created, 28);
         writer.write_bits(self.vehicle_id, 10);
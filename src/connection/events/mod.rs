@@ -1,65 +1,339 @@
+use std::{collections::VecDeque, sync::RwLock};
+
 use dashmap::DashMap;
 
-use crate::connection::events::event_types::Event;
+use crate::{app_state::MAX_CLIENTS, connection::events::event_types::Event};
 
 pub mod event_types;
 
-#[derive(Clone, Default)] 
+// Well under the 16 bit counter the client uses, so acks can always be matched back up to an event id.
+pub const EVENT_BUFFER_SIZE: usize = 4096;
+// Only 6 bits for the packed event count in a game packet.
+pub const MAX_EVENTS_PER_PACKET: usize = 63;
+
+// Bit per client id of who gets an event, None is everyone.
+#[derive(Clone, Debug)]
+struct LoggedEvent {
+    recipients: Option<u32>,
+    event: Event,
+}
+
+// The newest events, first_id is the id of the front one. Ids only ever go up.
+#[derive(Default)]
+struct EventLog {
+    first_id: u32,
+    events: VecDeque<LoggedEvent>,
+}
+
+impl EventLog {
+    fn next_id(&self) -> u32 {
+        self.first_id + self.events.len() as u32
+    }
+
+    // The events from next_id on that are for this client, with their ids.
+    fn events_for<'a>(&'a self, client: &'a PlayerEventManager) -> impl Iterator<Item = (u32, &'a Event)> {
+        let start = client.next_event.saturating_sub(self.first_id) as usize;
+
+        self.events
+            .iter()
+            .enumerate()
+            .skip(start)
+            .map(|(index, logged)| (self.first_id + index as u32, logged))
+            .filter(|(_, logged)| client.wants(logged))
+            .map(|(id, logged)| (id, &logged.event))
+    }
+}
+
+#[derive(Default)]
 pub struct EventManager {
     pub players: DashMap<u32, PlayerEventManager>,
-    // TODO: this never gets cleared until the server restarts. soooooooo...
-    // Also, if we issue a SHIT LOAD events, it doesnt get cleared.
-    pub global_events: DashMap<u32, Event>
+    log: RwLock<EventLog>,
 }
 
+// Each client counts only the events meant for it, so its 16 bit counter has no gaps even with targeted events.
 #[derive(Clone, Debug)]
 pub struct PlayerEventManager {
     pub player_id: u32,
-    pub recieved_events: u32
+    // What the client says it has, its own counter which starts at 0 on join.
+    pub recieved_events: u32,
+    // Id of the next event in the log this client hasn't got yet.
+    pub next_event: u32,
+}
+
+impl PlayerEventManager {
+    fn wants(&self, logged: &LoggedEvent) -> bool {
+        logged.recipients.is_none_or(|recipients| recipients & (1 << self.player_id) != 0)
+    }
 }
 
 impl EventManager {
     pub fn new() -> Self {
-        EventManager { 
-            players: DashMap::new(),
-            global_events: DashMap::new()
-        }
+        Self::default()
+    }
+
+    // Starts their event stream from now, with their counter back at 0. Nothing from before gets replayed,
+    // they need a snapshot of the current state instead.
+    pub fn add_player(&self, client_id: u32) {
+        let next_event = self.log.read().unwrap().next_id();
+
+        self.players.insert(
+            client_id,
+            PlayerEventManager {
+                player_id: client_id,
+                recieved_events: 0,
+                next_event,
+            },
+        );
     }
 
     pub fn num_global_events(&self) -> u32 {
-        self.global_events.len() as u32
+        self.log.read().unwrap().events.len() as u32
     }
 
     pub fn emit_globally(&self, event: Event) {
-        self.global_events.insert(self.global_events.len() as u32, event);
+        self.emit_globally_mult(vec![event]);
     }
 
     pub fn emit_globally_mult(&self, events: Vec<Event>) {
-        for event in events {
-            self.global_events.insert(self.global_events.len() as u32, event);
+        self.push(None, events);
+    }
+
+    // Only these clients get it, for private messages, sounds and UI updates that are just for them.
+    pub fn emit_to(&self, client_ids: &[u32], event: Event) {
+        let recipients = client_ids.iter().filter(|id| **id < MAX_CLIENTS).fold(0, |mask, id| mask | 1 << id);
+
+        self.push(Some(recipients), vec![event]);
+    }
+
+    fn push(&self, recipients: Option<u32>, events: Vec<Event>) {
+        let mut log = self.log.write().unwrap();
+
+        log.events.extend(events.into_iter().map(|event| LoggedEvent { recipients, event }));
+
+        // Full, so the oldest go even if someone hasn't got them yet. compact() catches them up.
+        while log.events.len() > EVENT_BUFFER_SIZE {
+            log.events.pop_front();
+            log.first_id += 1;
         }
     }
 
-    pub fn get_client_missing_events(&self, client_id: u32) -> Vec<(u32, Event)> {
-        if let Some(client) = self.players.get(&client_id) {
-            let event_count = self.global_events.len() as u32;
+    // How many events the client should have in total, as its 16 bit counter sees it.
+    pub fn client_event_count(&self, client_id: u32) -> u32 {
+        let log = self.log.read().unwrap();
+
+        self.players
+            .get(&client_id)
+            .map_or(0, |client| client.recieved_events + log.events_for(&client).count() as u32)
+    }
+
+    // The client only sends the low 16 bits of its counter, so take the closest count at or below what we've sent it.
+    pub fn acknowledge(&self, client_id: u32, recieved_events: u32) {
+        let log = self.log.read().unwrap();
+
+        let Some(mut client) = self.players.get_mut(&client_id) else {
+            return;
+        };
 
-            let missing_count = event_count.saturating_sub(client.recieved_events).min(63);
+        let pending = log.events_for(&client).map(|(id, _)| id).collect::<Vec<u32>>();
+        let total = client.recieved_events + pending.len() as u32;
+        let behind = (total as u16).wrapping_sub(recieved_events as u16) as u32;
 
-            let mut events = vec![];
-            let mut index = client.recieved_events;
+        // Packets can arrive out of order, never go backwards.
+        let newly_recieved = total.saturating_sub(behind).saturating_sub(client.recieved_events) as usize;
 
-            for _ in 0..missing_count {
-                if let Some(event) = self.global_events.get(&index) {
-                    events.push((index, event.clone()))
-                }
+        if newly_recieved > 0 {
+            client.next_event = pending[newly_recieved - 1] + 1;
+            client.recieved_events += newly_recieved as u32;
+        }
 
-                index += 1;
+        // Nothing left for them, so skip the events since that were for someone else. Otherwise they'd hold back
+        // compact() and get resynced when the buffer fills. Not if they've lost events, compact() has to catch that.
+        if newly_recieved == pending.len() && client.next_event >= log.first_id {
+            client.next_event = log.next_id();
+        }
+    }
+
+    // Drops every event all clients have. Anyone who fell out of the back of the buffer picks up at the oldest
+    // event we still have, their counter just carries on. Returns who had to skip events, their state is stale.
+    pub fn compact(&self) -> Vec<u32> {
+        let mut log = self.log.write().unwrap();
+        let mut resynced = vec![];
+
+        for mut client in self.players.iter_mut() {
+            if client.next_event < log.first_id {
+                client.next_event = log.first_id;
+                resynced.push(client.player_id);
             }
+        }
+
+        let oldest_needed = self.players.iter().map(|client| client.next_event).min().unwrap_or(log.next_id());
+
+        while log.first_id < oldest_needed && log.events.pop_front().is_some() {
+            log.first_id += 1;
+        }
+
+        resynced
+    }
+
+    // Returns (event number as the client counts it, event), at most a packet's worth.
+    pub fn get_client_missing_events(&self, client_id: u32) -> Vec<(u32, Event)> {
+        let log = self.log.read().unwrap();
+
+        let Some(client) = self.players.get(&client_id) else {
+            return vec![];
+        };
 
-            return events;
+        // Lost some, wait for compact() to move them on.
+        if client.next_event < log.first_id {
+            return vec![];
         }
 
-        vec![]
+        log.events_for(&client)
+            .take(MAX_EVENTS_PER_PACKET)
+            .enumerate()
+            .map(|(index, (_, event))| (client.recieved_events + index as u32, event.clone()))
+            .collect()
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::connection::events::event_types::team_door_state::EventTeamDoorState;
+
+    fn door(tick_created: i32) -> Event {
+        Event::TeamDoorState(EventTeamDoorState {
+            tick_created,
+            team_id: 0,
+            door_open: true,
+        })
+    }
+
+    fn emit(events: &EventManager, count: usize) {
+        for tick in 0..count {
+            events.emit_globally(door(tick as i32));
+        }
+    }
+
+    fn numbers(events: &EventManager, client_id: u32) -> Vec<u32> {
+        events.get_client_missing_events(client_id).into_iter().map(|(number, _)| number).collect()
+    }
+
+    #[test]
+    fn acks_match_up_across_the_16_bit_wrap() {
+        let events = EventManager::new();
+        events.add_player(0);
+        events.players.get_mut(&0).unwrap().recieved_events = 65534;
+
+        emit(&events, 4);
+        assert_eq!(events.client_event_count(0), 65538);
+        assert_eq!(numbers(&events, 0), vec![65534, 65535, 65536, 65537]);
+
+        // 65536 wrapped to 0.
+        events.acknowledge(0, 0);
+        assert_eq!(events.players.get(&0).unwrap().recieved_events, 65536);
+        assert_eq!(numbers(&events, 0), vec![65536, 65537]);
+
+        events.acknowledge(0, 65538 & 0xffff);
+        assert_eq!(events.players.get(&0).unwrap().recieved_events, 65538);
+        assert!(numbers(&events, 0).is_empty());
+    }
+
+    #[test]
+    fn late_acks_never_go_backwards() {
+        let events = EventManager::new();
+        events.add_player(0);
+
+        emit(&events, 5);
+
+        events.acknowledge(0, 3);
+        events.acknowledge(0, 1);
+
+        assert_eq!(events.players.get(&0).unwrap().recieved_events, 3);
+        assert_eq!(numbers(&events, 0), vec![3, 4]);
+    }
+
+    #[test]
+    fn acks_past_what_was_sent_are_ignored() {
+        let events = EventManager::new();
+        events.add_player(0);
+
+        emit(&events, 2);
+        events.acknowledge(0, 5);
+
+        assert_eq!(events.players.get(&0).unwrap().recieved_events, 0);
+        assert_eq!(numbers(&events, 0), vec![0, 1]);
+    }
+
+    #[test]
+    fn targeted_events_only_count_for_their_recipients() {
+        let events = EventManager::new();
+        events.add_player(0);
+        events.add_player(1);
+
+        events.emit_to(&[1], door(0));
+        events.emit_globally(door(1));
+
+        assert_eq!(events.client_event_count(0), 1);
+        assert_eq!(events.client_event_count(1), 2);
+
+        // Client 0 has everything meant for it, so it doesn't hold the log back for client 1's event.
+        events.acknowledge(0, 1);
+        assert_eq!(events.players.get(&0).unwrap().next_event, events.log.read().unwrap().next_id());
+
+        events.acknowledge(1, 2);
+        assert!(events.compact().is_empty());
+        assert_eq!(events.num_global_events(), 0);
+    }
+
+    #[test]
+    fn idle_clients_move_past_everyone_elses_events() {
+        let events = EventManager::new();
+        events.add_player(0);
+        events.add_player(1);
+
+        for tick in 0..EVENT_BUFFER_SIZE + 10 {
+            events.emit_to(&[1], door(tick as i32));
+            events.acknowledge(0, 0);
+            events.acknowledge(1, events.client_event_count(1));
+        }
+
+        assert!(events.compact().is_empty());
+    }
+
+    #[test]
+    fn clients_that_fall_out_of_the_buffer_get_resynced() {
+        let events = EventManager::new();
+        events.add_player(0);
+
+        emit(&events, EVENT_BUFFER_SIZE + 10);
+
+        // The oldest are gone, so nothing is sent until compact() moves them on.
+        assert!(numbers(&events, 0).is_empty());
+        assert_eq!(events.compact(), vec![0]);
+
+        // Their counter carries on from where it was, starting at the oldest event still held.
+        let missing = numbers(&events, 0);
+        assert_eq!(missing.len(), MAX_EVENTS_PER_PACKET);
+        assert_eq!(missing[0], 0);
+        assert_eq!(events.client_event_count(0), EVENT_BUFFER_SIZE as u32);
+    }
+
+    #[test]
+    fn compact_drops_what_everyone_has() {
+        let events = EventManager::new();
+        events.add_player(0);
+        events.add_player(1);
+
+        emit(&events, 10);
+
+        events.acknowledge(0, 10);
+        events.acknowledge(1, 4);
+        events.compact();
+        assert_eq!(events.num_global_events(), 6);
+
+        events.acknowledge(1, 10);
+        events.compact();
+        assert_eq!(events.num_global_events(), 0);
+    }
+}
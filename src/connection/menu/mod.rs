@@ -1,5 +1,11 @@
 pub mod lobby;
 pub mod enter_city;
+pub mod round_corp_stock;
+pub mod world_corp_application;
+pub mod world_corp_firing;
+pub mod world_corp_hiring;
+pub mod world_corp_requisition;
+pub mod world_corp_team;
 
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum MenuTypes {
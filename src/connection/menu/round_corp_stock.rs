@@ -0,0 +1,55 @@
+use crate::{app_state::{AppState, ChatType}, connection::{menu::MenuTypes, ClientConnection}, stocks::MAX_SHARES_PER_CORPORATION};
+
+pub fn handle_round_corp_stock_menu_action(menu_button: u32, connection: &mut ClientConnection, state: &AppState) {
+    // Spectators have no corporation to trade in.
+    let Some(price) = state.stocks.price(connection.team) else {
+        connection.menu = MenuTypes::Empty;
+
+        return;
+    };
+
+    let team = connection.team as usize;
+
+    match menu_button {
+        // Buy a share. Shares trade at the market price both ways, ratings only change store prices.
+        1 => {
+            if connection.money < price {
+                state.send_chat(ChatType::PrivateMessage, &format!("You need ${price} to buy a share."), connection.client_id as i32, 0);
+
+                return;
+            }
+
+            if connection.stocks[team] >= MAX_SHARES_PER_CORPORATION {
+                state.send_chat(ChatType::PrivateMessage, "You can't hold any more shares.", connection.client_id as i32, 0);
+
+                return;
+            }
+
+            connection.money -= price;
+            connection.stocks[team] += 1;
+            state.stocks.trade(connection.team, 1);
+        }
+
+        // Sell a share
+        2 => {
+            if connection.stocks[team] == 0 {
+                state.send_chat(ChatType::PrivateMessage, "You don't have any shares to sell.", connection.client_id as i32, 0);
+
+                return;
+            }
+
+            connection.money += price;
+            connection.stocks[team] -= 1;
+            state.stocks.trade(connection.team, -1);
+        }
+
+        _ => {
+            connection.menu = MenuTypes::Empty;
+
+            return;
+        }
+    }
+
+    connection.save_account(state);
+    connection.update_money(state);
+}
@@ -0,0 +1,7 @@
+use crate::{app_state::{AppState, ChatType}, connection::ClientConnection, corporations::actions};
+
+pub fn handle_world_corp_application_menu_action(_menu_button: u32, connection: &mut ClientConnection, state: &AppState) {
+    let message = actions::apply(state, connection.account_id, None);
+
+    state.send_chat(ChatType::PrivateMessage, &message, connection.client_id as i32, 0);
+}
@@ -0,0 +1,11 @@
+use crate::{app_state::{AppState, ChatType}, connection::{menu::MenuTypes, ClientConnection}, corporations::actions};
+
+pub fn handle_world_corp_hiring_menu_action(_menu_button: u32, connection: &mut ClientConnection, state: &AppState) {
+    if !state.corporations.is_manager(connection.account_id) {
+        connection.menu = MenuTypes::WorldEmptyCorp;
+    }
+
+    let message = actions::hire(state, connection.account_id, None);
+
+    state.send_chat(ChatType::PrivateMessage, &message, connection.client_id as i32, 0);
+}
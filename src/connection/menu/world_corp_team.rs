@@ -0,0 +1,13 @@
+use crate::{app_state::{AppState, ChatType}, connection::{menu::MenuTypes, ClientConnection}, corporations::actions};
+
+pub fn handle_world_corp_team_menu_action(_menu_button: u32, connection: &mut ClientConnection, state: &AppState) {
+    if state.corporations.employer_of(connection.account_id).is_none() {
+        connection.menu = MenuTypes::WorldEmptyCorp;
+
+        return;
+    }
+
+    let message = actions::team(state, connection.account_id);
+
+    state.send_chat(ChatType::PrivateMessage, &message, connection.client_id as i32, 0);
+}
@@ -1,50 +1,29 @@
 use crossbeam::channel::{Receiver, Sender};
-use std::{net::SocketAddr, sync::Arc, time::SystemTime};
+use std::{collections::VecDeque, net::SocketAddr, sync::Arc, time::{Instant, SystemTime}};
 use tokio::task::JoinHandle;
 
 use crate::{
-    app_state::ChatType, commands::parse_command, connection::{
-        events::{event_types::{update_player::EventUpdatePlayer, update_player_round::EventUpdatePlayerRound, Event}, PlayerEventManager},
-        menu::{enter_city::handle_enter_city_menu_action, lobby::handle_lobby_menu_action, menu_from_num, MenuTypes},
+    access::permissions::Role, app_state::ChatType, chat::send_player_chat, commands::parse_command, connection::{
+        events::{event_types::{update_player::EventUpdatePlayer, update_player_round::EventUpdatePlayerRound, Event}},
+        menu::{
+            enter_city::handle_enter_city_menu_action, lobby::handle_lobby_menu_action, menu_from_num, round_corp_stock::handle_round_corp_stock_menu_action,
+            world_corp_application::handle_world_corp_application_menu_action, world_corp_firing::handle_world_corp_firing_menu_action,
+            world_corp_hiring::handle_world_corp_hiring_menu_action, world_corp_requisition::handle_world_corp_requisition_menu_action,
+            world_corp_team::handle_world_corp_team_menu_action, MenuTypes,
+        },
     }, items::Item, packets::{
-        clientbound::game::{ClientboundGamePacket, ClientboundGamePacketCorporationMoney}, masterserver::auth::MasterServerAuthPacket, serverbound::game::actions::ServerboundGameAction, Encodable, PacketType, Team
-    }, voice::PlayerVoice, world::{quaternion::Quaternion, vector::Vector}, AppState
+        clientbound::game::{ClientboundGamePacket, ClientboundGamePacketCorporationMoney}, masterserver::auth::MasterServerAuthPacket, serverbound::game::actions::ServerboundGameAction, Encodable, GameMode, PacketType, Team
+    }, ratings::store_price, srk_parser::SrkPlayerData, stocks::StockHoldings, storage::PlayerStore, voice::PlayerVoice, world::{quaternion::Quaternion, vector::Vector}, AppState, TICKS_PER_SECOND
 };
 
+pub mod customization;
 pub mod events;
 pub mod menu;
 pub mod packets;
 
-#[derive(Debug, Clone)]
-pub struct CharacterCustomization {
-    pub gender: i32,
-    pub head: i32,
-    pub skin: i32,
-    pub hair_color: i32,
-    pub hair_style: i32,
-    pub eye_color: i32,
-    pub model: i32,
-    pub necklace: i32,
-    pub suit_color: i32,
-    pub tie_color: i32,
-}
+pub use customization::CharacterCustomization;
 
-impl Default for CharacterCustomization {
-    fn default() -> Self {
-        Self {
-            gender: 0,
-            head: 4,
-            skin: 2,
-            hair_color: 4,
-            hair_style: 6,
-            eye_color: 6,
-            model: 1,
-            necklace: 0,
-            suit_color: 0,
-            tie_color: 1,
-        }
-    }
-}
+use customization::parse_team_uniforms;
 
 #[derive(Debug, Clone)]
 pub struct ClientConnection {
@@ -56,22 +35,30 @@ pub struct ClientConnection {
     pub customization: CharacterCustomization,
     pub team: Team,
     pub money: i32,
+    pub corp_rating: u32,
+    pub crim_rating: u32,
+    pub play_time_tick: i32,
+    pub stocks: StockHoldings,
     pub menu: MenuTypes,
     pub camera_pos: Vector,
+    // When their recent messages were sent, for chat_rate_limit.
+    pub chat_times: VecDeque<Instant>,
 
     pub tx_socket: Sender<(Vec<u8>, SocketAddr)>,
     pub last_packet: SystemTime,
+    pub protocol_errors: u32,
     pub address: SocketAddr,
     pub username: String,
     pub account_id: u32,
     pub phone_number: u32,
+    pub role: Role,
     tx_sender: Sender<Vec<u8>>,
     tx_receiver: Receiver<Vec<u8>>,
     tx_handle: Arc<Option<JoinHandle<()>>>,
 }
 
 impl ClientConnection {
-    pub fn from_auth(address: SocketAddr, tx_socket: Sender<(Vec<u8>, SocketAddr)>, auth: &MasterServerAuthPacket, connection_id: u32) -> Self {
+    pub fn from_auth(address: SocketAddr, tx_socket: Sender<(Vec<u8>, SocketAddr)>, auth: &MasterServerAuthPacket, connection_id: u32, network_tick: i32) -> Self {
         let (tx_sender, tx_receiver) = crossbeam::channel::unbounded();
 
         let username = if auth.account_id == 1_000_002 {
@@ -89,15 +76,22 @@ impl ClientConnection {
             customization: CharacterCustomization::default(),
             team: Team::Spectator,
             money: 0,
+            corp_rating: 0,
+            crim_rating: 0,
+            play_time_tick: network_tick,
+            stocks: StockHoldings::default(),
             menu: MenuTypes::Lobby,
             camera_pos: Vector::default(),
+            chat_times: VecDeque::new(),
 
             tx_socket,
             last_packet: SystemTime::now(),
+            protocol_errors: 0,
             address,
             username,
             account_id: auth.account_id,
             phone_number: auth.phone_number,
+            role: Role::Player,
             tx_sender,
             tx_receiver,
             tx_handle: Arc::new(None),
@@ -109,13 +103,7 @@ impl ClientConnection {
     }
 
     pub fn handle_join(&self, state: &AppState) {
-        state.events.players.insert(
-            self.client_id,
-            PlayerEventManager {
-                player_id: self.client_id,
-                recieved_events: 0,
-            },
-        );
+        state.events.add_player(self.client_id);
 
         state.voices.client_voices.insert(
             self.client_id,
@@ -123,6 +111,8 @@ impl ClientConnection {
                 client_id: self.client_id,
                 enabled: false,
                 frames: vec![],
+                position: self.camera_pos,
+                last_spoke: None,
             },
         );
 
@@ -133,13 +123,16 @@ impl ClientConnection {
                 item_id: self.client_id,
                 pos: Vector::zero(),
                 rot: Quaternion::zero(),
+                despawn_tick: None,
             },
         );
 
         state.send_chat(ChatType::Announce, &format!("{} joined!", self.username), -1, 0);
     }
 
-    pub fn handle_leave(&self, state: &AppState) {
+    pub fn handle_leave(&mut self, state: &AppState) {
+        self.save_account(state);
+
         state.events.players.remove(&self.client_id);
         state.voices.client_voices.remove(&self.client_id);
         state.items.items.remove(&self.client_id);
@@ -182,6 +175,12 @@ impl ClientConnection {
             received_actions: self.received_actions,
             last_sdl_tick: self.last_sdl_tick,
             money: self.money,
+            team_budget: state.corporations.budget(self.team),
+            is_manager: state.corporations.is_manager(self.account_id),
+            corp_rating: self.corp_rating,
+            crim_rating: self.crim_rating,
+            // Only set while they're waiting to respawn, and nobody can die here.
+            spawn_timer: 0,
 
             follow_pos: self.camera_pos,
 
@@ -197,47 +196,104 @@ impl ClientConnection {
         self.send_data(game.encode(state));
     }
 
-    pub fn update_player(&self, state: &AppState) {
-        let event_update = Event::UpdatePlayer(EventUpdatePlayer {
+    pub fn player_event(&self, state: &AppState) -> Event {
+        Event::UpdatePlayer(EventUpdatePlayer {
             tick_created: state.network_tick(),
             client_id: self.client_id,
             active: true,
-            customization: self.customization.clone(),
+            customization: self.appearance(state),
             human_id: self.human_id.unwrap_or(-1),
             is_bot: false,
             team: self.team,
             name: self.username.clone(),
-        });
+        })
+    }
+
+    // Their own look with their team's uniform on, if it has one.
+    pub fn appearance(&self, state: &AppState) -> CharacterCustomization {
+        let uniforms = parse_team_uniforms(&state.config().team_uniforms);
 
-        state.events.emit_globally(event_update);
+        self.customization.wearing(uniforms.get(&self.team))
     }
 
-    pub fn update_money(&self, state: &AppState) {
-        let event_update = Event::UpdatePlayerRound(EventUpdatePlayerRound {
+    pub fn round_event(&self, state: &AppState) -> Event {
+        Event::UpdatePlayerRound(EventUpdatePlayerRound {
             tick_created: state.network_tick(),
             client_id: self.client_id,
             money: self.money,
             phone_number: self.phone_number,
-            stocks: 0,
-        });
+            stocks: self.shares_in(self.team) as i32,
+        })
+    }
+
+    pub fn update_player(&self, state: &AppState) {
+        state.events.emit_globally(self.player_event(state));
+    }
+
+    pub fn update_money(&self, state: &AppState) {
+        state.events.emit_globally(self.round_event(state));
+    }
+
+    pub fn store_price(&self, base_price: i32) -> i32 {
+        store_price(base_price, self.corp_rating, self.crim_rating)
+    }
+
+    pub fn load_account(&mut self, account: &SrkPlayerData) {
+        self.money = account.money;
+        self.corp_rating = account.corp_rating;
+        self.crim_rating = account.crim_rating;
+        self.stocks = account.stocks;
 
-        state.events.emit_globally(event_update);
+        if let Some(customization) = CharacterCustomization::from_saved(account.appearance_0, account.appearance_1) {
+            self.customization = customization;
+        }
+    }
+
+    // Writes our economy back into the SRK record, the caller decides when the file gets saved.
+    pub fn store_account(&mut self, data: &mut dyn PlayerStore, network_tick: i32, gamemode: GameMode) {
+        let played_seconds = (network_tick - self.play_time_tick).max(0) / TICKS_PER_SECOND;
+
+        if let Some(mut account) = data.get_account(self.account_id) {
+            account.money = self.money;
+            account.corp_rating = self.corp_rating;
+            account.crim_rating = self.crim_rating;
+            account.stocks = self.stocks;
+            (account.appearance_0, account.appearance_1) = self.customization.to_saved();
+            account.play_time = account.play_time.saturating_add(played_seconds as u32);
+
+            data.update_account(&account);
+            data.add_stat(self.account_id, gamemode, "play_time", played_seconds as i64);
+        }
+
+        // Only move forward by whole seconds so the remainder counts towards the next save.
+        self.play_time_tick += played_seconds * TICKS_PER_SECOND;
+    }
+
+    pub fn save_account(&mut self, state: &AppState) {
+        let mut data = state.player_store.lock().unwrap();
+
+        data.begin_batch();
+        self.store_account(data.as_mut(), state.network_tick(), state.config().gamemode);
+        data.end_batch();
+
+        data.save();
+    }
+
+    pub fn shares_in(&self, team: Team) -> u8 {
+        self.stocks.get(team as usize).copied().unwrap_or(0)
     }
 
     pub fn send_data(&self, data: Vec<u8>) {
-        let header = b"7DFP";
-        let mut vec = Vec::with_capacity(header.len() + data.len());
-        vec.extend_from_slice(header);
-        vec.extend_from_slice(&data);
+        let _ = self.tx_sender.send(with_header(data));
+    }
 
-        let _ = self.tx_sender.send(vec);
+    pub fn send_data_immediately(&self, data: Vec<u8>) {
+        let _ = self.tx_socket.send((with_header(data), self.address));
     }
 
     pub async fn handle_packet(&mut self, packet: PacketType, state: &AppState) {
         if let PacketType::ServerboundGamePacket(ref game_packet) = packet {
-            if let Some(mut ev) = state.events.players.get_mut(&self.client_id) {
-                ev.recieved_events = game_packet.recieved_events;
-            }
+            state.events.acknowledge(self.client_id, game_packet.recieved_events);
 
             let new_action_amount = game_packet.actions.len() as u32;
             self.received_actions = (self.received_actions + new_action_amount) % 64;
@@ -249,23 +305,37 @@ impl ClientConnection {
                 item.pos = self.camera_pos;
             }
 
-            for event in game_packet.actions.clone().into_iter() {
-                if let ServerboundGameAction::Chat(ref chat) = event {
-                    println!("{} [>] {}", self.username, chat.message);
+            // In world mode your team is whichever corporation employs you.
+            if state.config().gamemode == GameMode::World {
+                let team = state.corporations.employer_of(self.account_id).unwrap_or(Team::Spectator);
 
-                    if !parse_command(self, chat.message.clone(), state) {
-                        state.send_chat(ChatType::Announce, &chat.message, self.client_id as i32, chat.volume as i32);
-                    }
+                if team != self.team {
+                    self.team = team;
+                    self.update_player(state);
+                }
+            }
+
+            for event in game_packet.actions.clone().into_iter() {
+                if let ServerboundGameAction::Chat(ref chat) = event
+                    && !parse_command(self, chat.message.clone(), state)
+                {
+                    send_player_chat(self, &chat.message, chat.volume, state);
                 }
 
                 if let ServerboundGameAction::Menu(ref menu) = event {
                     let menu_type = menu_from_num(menu.menu);
 
-                    println!("Menu {menu:?} - type {menu_type:?}");
+                    debug!("menu", "Menu {menu:?} - type {menu_type:?}");
 
                     match menu_type {
                         MenuTypes::Lobby => handle_lobby_menu_action(menu.button, self, state),
                         MenuTypes::EnterCity => handle_enter_city_menu_action(menu.button, self, state),
+                        MenuTypes::RoundCorpStock => handle_round_corp_stock_menu_action(menu.button, self, state),
+                        MenuTypes::WorldCorpApplication => handle_world_corp_application_menu_action(menu.button, self, state),
+                        MenuTypes::WorldCorpHiring => handle_world_corp_hiring_menu_action(menu.button, self, state),
+                        MenuTypes::WorldCorpFiring => handle_world_corp_firing_menu_action(menu.button, self, state),
+                        MenuTypes::WorldCorpTeam => handle_world_corp_team_menu_action(menu.button, self, state),
+                        MenuTypes::WorldCorpRequistion => handle_world_corp_requisition_menu_action(menu.button, self, state),
                         _ => {}
                     }
                 }
@@ -273,3 +343,12 @@ impl ClientConnection {
         }
     }
 }
+
+fn with_header(data: Vec<u8>) -> Vec<u8> {
+    let header = b"7DFP";
+    let mut vec = Vec::with_capacity(header.len() + data.len());
+    vec.extend_from_slice(header);
+    vec.extend_from_slice(&data);
+
+    vec
+}
@@ -1,4 +1,4 @@
-use crate::{connection::menu::MenuTypes, packets::{buf_writer::AlexBufWriter, get_sun_time, Encodable, GameState, WriterEncodable}, world::vector::Vector};
+use crate::{connection::menu::MenuTypes, packets::{buf_writer::AlexBufWriter, get_sun_time, Encodable, GameState, WriterEncodable}, voice::MAX_SPEAKERS, world::vector::Vector};
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct ClientboundGamePacket {
@@ -9,6 +9,11 @@ pub struct ClientboundGamePacket {
     pub last_sdl_tick: u32,
     pub menu_type: MenuTypes,
     pub money: i32,
+    pub team_budget: i32,
+    pub is_manager: bool,
+    pub corp_rating: u32,
+    pub crim_rating: u32,
+    pub spawn_timer: u16,
 
     pub follow_pos: Vector,
 
@@ -68,17 +73,17 @@ impl Encodable for ClientboundGamePacket {
         writer.write_bytes(&0u32.to_le_bytes()); // Head vel Y
         writer.write_bytes(&0u32.to_le_bytes()); // Head vel Z
 
-        writer.write_bits(0, 1); // Can see manager tab
+        writer.write_bits(self.is_manager as i32, 1); // Can see manager tab
         writer.write_bits(self.menu_type as i32, 8); // Player menu tab
         writer.write_bits(0, 16);
 
         writer.write_bytes(&self.money.to_le_bytes()); // Money
         writer.write_bytes(&0u32.to_le_bytes()); // Team Money
-        writer.write_bytes(&0u32.to_le_bytes()); // Team Budget
-        writer.write_bytes(&0u32.to_le_bytes()); // Corporate Rating
-        writer.write_bytes(&24u32.to_le_bytes()); // Criminal Rating
+        writer.write_bytes(&self.team_budget.to_le_bytes()); // Team Budget
+        writer.write_bytes(&self.corp_rating.to_le_bytes()); // Corporate Rating
+        writer.write_bytes(&self.crim_rating.to_le_bytes()); // Criminal Rating
 
-        writer.write_bits(0, 16); // Player spawn timer
+        writer.write_bits(self.spawn_timer as i32, 16); // Player spawn timer
         writer.write_bits(self.received_actions as i32, 8); // Player number of actions
 
         writer.write_bits(0, 10); // Player human oldHealth
@@ -139,35 +144,36 @@ impl Encodable for ClientboundGamePacket {
         writer.write_bits(3, 2);
         writer.write_bits(1, 2);
 
-        let voices = state.voices.client_voices.iter();
+        let heard = {
+            let config = state.config();
 
-        let mut wrote_count = 0;
-        for voice in voices {
-            if voice.enabled && wrote_count < 8 {
-                writer.write_bits(1, 1);
-                writer.write_bits(voice.client_id as i32, 8);
-                writer.write_bits(-1, 8);
-                writer.write_bits(-1, 8);
-                
-                for i in 0..4 {
-                    let frame = &voice.frames[i];
+            if config.voice_chat {
+                state.voices.heard_by(self.client_id, self.follow_pos, config.voice_min, config.voice_boost)
+            } else {
+                vec![]
+            }
+        };
 
-                    writer.write_bits(frame.index as i32, 6);
-                    writer.write_bits(frame.size as i32, 11);
-                    writer.write_bits(frame.volume as i32, 2);
+        for voice in &heard {
+            writer.write_bits(1, 1);
+            writer.write_bits(voice.client_id as i32, 8);
+            writer.write_bits(-1, 8);
+            writer.write_bits(-1, 8);
 
-                    writer.write_bytes(&frame.data);
-                }
+            for frame in &voice.frames {
+                writer.write_bits(frame.index as i32, 6);
+                writer.write_bits(frame.size as i32, 11);
+                writer.write_bits(frame.volume as i32, 2);
 
-                wrote_count += 1;
+                writer.write_bytes(&frame.data);
             }
         }
 
-        for _ in wrote_count..8 {
+        for _ in heard.len()..MAX_SPEAKERS {
             writer.write_bits(0, 1); // Voice is active
         }
 
-        writer.write_bits(state.events.num_global_events() as i32, 16); // Total number of server events
+        writer.write_bits(state.events.client_event_count(self.client_id) as i32, 16); // Total number of server events
 
         let missing = state.events.get_client_missing_events(self.client_id);
         writer.write_bits(missing.len() as i32, 6); // Packed server event count
@@ -176,7 +182,7 @@ impl Encodable for ClientboundGamePacket {
             writer.write_bits(missing[0].0 as i32, 16); // starting event id
 
             for (_, event) in missing {
-                event.encode(state, &mut writer);
+                event.encode(&mut writer);
             }
         } else {
             writer.write_bits(0, 16);
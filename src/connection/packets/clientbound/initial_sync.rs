@@ -16,7 +16,7 @@ impl Encodable for ClientboundInitialSyncPacket {
 
         writer.write_byte(0x06);
         writer.write_bytes(&self.round_number.to_le_bytes());
-        writer.write_bits(state.config.gamemode as i32, 4);
+        writer.write_bits(state.config().gamemode as i32, 4);
         writer.write_bits(self.weekly_enabled as i32, 4);
         writer.write_byte(self.weekday);
 
@@ -25,7 +25,7 @@ impl Encodable for ClientboundInitialSyncPacket {
         writer.write_bytes(&self.sun_angle.to_le_bytes());
         writer.write_bytes(&self.sun_axial_tilt.to_le_bytes());
 
-        if state.config.gamemode == GameMode::Versus {
+        if state.config().gamemode == GameMode::Versus {
             writer.write_byte(self.versus_movedelay.unwrap_or(0));
         }
 
@@ -7,16 +7,19 @@ pub struct ClientboundKickPacket {
 
 impl Encodable for ClientboundKickPacket {
     fn encode(&self, _state: &crate::AppState) -> Vec<u8> {
-        if self.reason.len() > 63 {
-            panic!("Kick reason exceeds maximum length of 32 bytes");
+        // The client only takes 63 bytes of reason, cut it down instead of sending garbage.
+        let mut reason = self.reason.clone();
+
+        while reason.len() > 63 {
+            reason.pop();
         }
 
         let mut buf = vec![];
         
         buf.push(0x03); // header
         
-        buf.push(self.reason.len() as u8); // length of the reason string
-        buf.extend_from_slice(self.reason.as_bytes()); // reason string
+        buf.push(reason.len() as u8); // length of the reason string
+        buf.extend_from_slice(reason.as_bytes()); // reason string
 
         buf
     }
@@ -18,14 +18,14 @@ impl Encodable for ServerInfo {
         writer.write_byte(0x26);
         writer.write_bytes(&self.timestamp.to_le_bytes());
 
-        writer.write_bits(state.config.gamemode as i32, 4);
+        writer.write_bits(state.config().gamemode as i32, 4);
         writer.write_bits(least_significant(self.current_players) as i32, 4);
         writer.write_bits(most_significant(self.current_players) as i32, 4);
-        writer.write_bits(least_significant(state.config.max_players) as i32, 4);
-        writer.write_bits(most_significant(state.config.max_players) as i32, 4);
+        writer.write_bits(least_significant(state.config().max_players) as i32, 4);
+        writer.write_bits(most_significant(state.config().max_players) as i32, 4);
         writer.write_bits(9, 4);
 
-        writer.write_string(state.config.server_name.clone());
+        writer.write_string(state.config().server_name.clone());
         writer.write_bytes(&SERVER_IDENTIFIER.to_le_bytes());
         writer.write_bytes(
             &self
@@ -34,9 +34,9 @@ impl Encodable for ServerInfo {
                 .map(|s| s.parse::<u8>().unwrap())
                 .collect::<Vec<u8>>(),
         );
-        writer.write_bytes(&state.config.port.to_le_bytes());
+        writer.write_bytes(&state.config().port.to_le_bytes());
 
-        writer.write_bits(!state.config.server_password.is_empty() as i32, 1);
+        writer.write_bits(!state.config().server_password.is_empty() as i32, 1);
         writer.write_bits(0x04, 8);
         writer.write_bits(0x47, 8);
 
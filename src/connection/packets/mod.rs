@@ -1,4 +1,4 @@
-use std::net::SocketAddr;
+use std::{net::SocketAddr, str::FromStr};
 
 use serde::{Deserialize, Serialize};
 
@@ -24,7 +24,7 @@ pub enum GameMode {
     None = 8
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 #[repr(u8)]
 pub enum Team {
     Goldmen = 0,
@@ -36,6 +36,22 @@ pub enum Team {
     Spectator = 17
 }
 
+impl FromStr for Team {
+    type Err = ();
+
+    fn from_str(team: &str) -> Result<Self, Self::Err> {
+        match team.trim().to_lowercase().as_str() {
+            "goldmen" => Ok(Team::Goldmen),
+            "monsota" => Ok(Team::Monsota),
+            "oxs" => Ok(Team::OXS),
+            "nexaco" => Ok(Team::Nexaco),
+            "pentacom" => Ok(Team::Pentacom),
+            "spectator" => Ok(Team::Spectator),
+            _ => Err(()),
+        }
+    }
+}
+
 pub fn get_sun_time(hour: i32, minute: i32) -> i32 {
     let hour_time: i32 = hour.clamp(0, 24) * 216000;
     let minute_time: i32 = minute.clamp(0, 59) * 3600;
@@ -58,8 +74,9 @@ pub trait Encodable {
     fn encode(&self, state: &AppState) -> Vec<u8>;
 }
 
+// Written into a bigger packet, like the events in a game packet. Nothing here needs the state, so it can be tested on its own.
 pub trait WriterEncodable {
-    fn encode(&self, state: &AppState, writer: &mut AlexBufWriter);
+    fn encode(&self, writer: &mut AlexBufWriter);
 }
 
 pub trait StatelessEncodable {
@@ -79,11 +96,29 @@ pub enum PacketType {
     ServerboundJoinRequest(ServerboundJoinRequest),
     ServerboundInfoRequest(ServerboundInfoRequest),
     ServerboundGamePacket(Box<ServerboundGamePacket>),
-    MasterServerAuthPacket(MasterServerAuthPacket)
+    MasterServerAuthPacket(MasterServerAuthPacket),
+    MasterServerKeepAlive
 }
 
 pub fn decode_packet(data: Vec<u8>, src: SocketAddr, state: &AppState) -> Option<PacketType> {
+    state.metrics.record_in(src, data.len());
+
+    let packet = decode_packet_data(data, src, state);
+
+    // Anything a connected client sends that we can't make sense of counts against them.
+    if packet.is_none() || packet == Some(PacketType::Unknown) {
+        state.protocol_error(src);
+    }
+
+    packet
+}
+
+fn decode_packet_data(data: Vec<u8>, src: SocketAddr, state: &AppState) -> Option<PacketType> {
     // This is ALWAYS 7DFP\0
+    if data.len() < 5 {
+        return None;
+    }
+
     let mut data = data.clone();
     let _header = data.drain(..4).collect::<Vec<u8>>();
     let packet_type: u8 = data.drain(..1).as_slice().to_vec()[0];
@@ -95,18 +130,17 @@ pub fn decode_packet(data: Vec<u8>, src: SocketAddr, state: &AppState) -> Option
         2 => PacketType::ServerboundJoinRequest(ServerboundJoinRequest::decode(data, src, state)?),
         7 => PacketType::ServerboundLeave,
         4 => PacketType::ServerboundGamePacket(Box::new(ServerboundGamePacket::decode(data, src, state)?)),
-        b'@' => { PacketType::Unknown }
+        b'@' => { PacketType::MasterServerKeepAlive }
         66 => PacketType::MasterServerAuthPacket(MasterServerAuthPacket::decode(data, src, state)?),
         _ => {
-            println!("Unknown packet type: {packet_type}");
-            println!("Data: {data:?}");
-            println!("String: {}", String::from_utf8_lossy(&data));
+            debug!("packets", "Unknown packet type {packet_type} from {src}, {} bytes", data.len());
+            trace!("packets", "{data:?}");
             PacketType::Unknown
         }
     };
 
     if let PacketType::MasterServerAuthPacket(_) = packet && src != state.masterserver.address {
-        println!("[AUTH] {src} tried to send a Masterserver packet! Rejecting!");
+        warn!("auth", "{src} tried to send a Masterserver packet! Rejecting!");
 
         return None
     }
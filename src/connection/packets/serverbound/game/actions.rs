@@ -50,7 +50,7 @@ pub fn decode_actions(reader: &mut AlexBufReader, num_actions: u32) -> Option<Ve
                 b: reader.read_u32()?,
             }),
             _ => {
-                println!("Received invalid action type {action_type:?}");
+                debug!("packets", "Received invalid action type {action_type:?}");
 
                 ServerboundGameAction::Unknown
             }
@@ -99,20 +99,19 @@ impl Decodable for ServerboundGamePacket {
 
         let mut actions = decode_actions(&mut reader, packet_action_count)?;
 
-        if let Some(connection) = state.connections.get_mut(&src) {
-            let skip_count = connection.received_actions.saturating_sub(total_actions);
-
-            if skip_count > 0 {
-                if skip_count >= actions.len() as u32 {
-                    actions.clear();
-                } else {
-                    actions = actions[skip_count as usize..].to_vec()
-                }
+        let connection = state.connections.get_mut(&src)?;
+        let skip_count = connection.received_actions.saturating_sub(total_actions);
+
+        if skip_count > 0 {
+            if skip_count >= actions.len() as u32 {
+                actions.clear();
+            } else {
+                actions = actions[skip_count as usize..].to_vec()
             }
-        } else {
-            return None;
         }
 
+        drop(connection);
+
         let voice_data =
             decode_voice_data(&mut reader, state, &state.connections.get(&src).unwrap())?;
 
@@ -1,3 +1,5 @@
+use std::time::Instant;
+
 use crate::{app_state::AppState, connection::ClientConnection, packets::buf_reader::AlexBufReader};
 
 #[derive(Debug, Clone, PartialEq)]
@@ -32,9 +34,17 @@ pub fn decode_voice_data(reader: &mut AlexBufReader, state: &AppState, connectio
 
     let is_silenced = reader.boundscheck_read_bits(1)? != 0;
 
+    // Muted speakers are never relayed, but their packet still has to be read past.
+    let speaking = !is_silenced && !state.voices.is_muted(connection.account_id);
+
     if let Some(mut voice) = state.voices.client_voices.get_mut(&connection.client_id) {
-        voice.enabled = !is_silenced;
+        voice.enabled = speaking;
         voice.frames = frames.clone().to_vec();
+        voice.position = connection.camera_pos;
+
+        if speaking {
+            voice.last_spoke = Some(Instant::now());
+        }
     }
 
     Some(ServerboundGameVoiceData {
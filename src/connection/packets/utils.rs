@@ -38,6 +38,88 @@ pub fn read_float_radians_from_packet(raw_value: u32, num_bits: u32) -> f32 {
     }
 }
 
+// Closest plain letters for the accented ones people actually type, everything else non ASCII gets dropped.
+fn transliterate(char: char) -> Option<&'static str> {
+    Some(match char {
+        'à' | 'á' | 'â' | 'ã' | 'ä' | 'å' | 'ā' | 'ą' => "a",
+        'À' | 'Á' | 'Â' | 'Ã' | 'Ä' | 'Å' | 'Ā' | 'Ą' => "A",
+        'æ' => "ae",
+        'Æ' => "AE",
+        'ç' | 'ć' | 'č' => "c",
+        'Ç' | 'Ć' | 'Č' => "C",
+        'ď' | 'đ' => "d",
+        'Ď' | 'Đ' => "D",
+        'è' | 'é' | 'ê' | 'ë' | 'ē' | 'ę' | 'ě' => "e",
+        'È' | 'É' | 'Ê' | 'Ë' | 'Ē' | 'Ę' | 'Ě' => "E",
+        'ğ' => "g",
+        'Ğ' => "G",
+        'ì' | 'í' | 'î' | 'ï' | 'ī' | 'ı' => "i",
+        'Ì' | 'Í' | 'Î' | 'Ï' | 'Ī' | 'İ' => "I",
+        'ł' => "l",
+        'Ł' => "L",
+        'ñ' | 'ń' | 'ň' => "n",
+        'Ñ' | 'Ń' | 'Ň' => "N",
+        'ò' | 'ó' | 'ô' | 'õ' | 'ö' | 'ø' | 'ō' | 'ő' => "o",
+        'Ò' | 'Ó' | 'Ô' | 'Õ' | 'Ö' | 'Ø' | 'Ō' | 'Ő' => "O",
+        'œ' => "oe",
+        'Œ' => "OE",
+        'ř' => "r",
+        'Ř' => "R",
+        'ś' | 'š' | 'ş' => "s",
+        'Ś' | 'Š' | 'Ş' => "S",
+        'ß' => "ss",
+        'ť' | 'ţ' => "t",
+        'Ť' | 'Ţ' => "T",
+        'ù' | 'ú' | 'û' | 'ü' | 'ū' | 'ů' | 'ű' => "u",
+        'Ù' | 'Ú' | 'Û' | 'Ü' | 'Ū' | 'Ů' | 'Ű' => "U",
+        'ý' | 'ÿ' => "y",
+        'Ý' | 'Ÿ' => "Y",
+        'ź' | 'ż' | 'ž' => "z",
+        'Ź' | 'Ż' | 'Ž' => "Z",
+        '‘' | '’' | '‚' | '′' => "'",
+        '“' | '”' | '„' | '″' => "\"",
+        '–' | '—' | '−' => "-",
+        '…' => "...",
+        '\u{a0}' | '\t' => " ",
+        _ => return None,
+    })
+}
+
+// The client reads text 7 bits a character, so anything that isn't printable ASCII would come out as garbage.
+pub fn to_ascii(input: &str) -> String {
+    let mut output = String::with_capacity(input.len());
+
+    for char in input.chars() {
+        if char.is_ascii() && !char.is_ascii_control() {
+            output.push(char);
+        } else if let Some(replacement) = transliterate(char) {
+            output.push_str(replacement);
+        }
+    }
+
+    output
+}
+
+// Splits text into pieces of at most max_len, breaking at spaces where it can.
+pub fn split_text(text: &str, max_len: usize) -> Vec<String> {
+    let mut pieces = vec![];
+    let mut rest = text.trim();
+
+    while rest.len() > max_len {
+        // Only ever called on ASCII, so any byte index is a char boundary.
+        let cut = rest[..=max_len].rfind(' ').filter(|cut| *cut > 0).unwrap_or(max_len);
+
+        pieces.push(rest[..cut].trim_end().to_string());
+        rest = rest[cut..].trim_start();
+    }
+
+    if !rest.is_empty() || pieces.is_empty() {
+        pieces.push(rest.to_string());
+    }
+
+    pieces
+}
+
 pub fn limited_string(input: &str, capacity: usize) -> Vec<u8> {
     let mut output = vec![0; capacity];
     let bytes = input.as_bytes();
@@ -71,4 +153,54 @@ pub fn read_u8(buf: &mut Vec<u8>) -> u8 {
         panic!("Buffer too short to read u8");
     }
     buf.drain(..1).as_slice().to_vec()[0]
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn to_ascii_keeps_printable_ascii() {
+        assert_eq!(to_ascii("Hello, world! ~123"), "Hello, world! ~123");
+    }
+
+    #[test]
+    fn to_ascii_transliterates_and_drops_the_rest() {
+        assert_eq!(to_ascii("Zoë Straße — “hi”…"), "Zoe Strasse - \"hi\"...");
+        assert_eq!(to_ascii("日本 ok 🙂"), " ok ");
+    }
+
+    #[test]
+    fn to_ascii_drops_control_characters() {
+        assert_eq!(to_ascii("a\nb\0c\x7f"), "abc");
+        assert_eq!(to_ascii("a\tb"), "a b");
+    }
+
+    #[test]
+    fn split_text_leaves_short_text_alone() {
+        assert_eq!(split_text("  short  ", 10), vec!["short"]);
+        assert_eq!(split_text("", 10), vec![""]);
+    }
+
+    #[test]
+    fn split_text_breaks_at_spaces() {
+        assert_eq!(split_text("the quick brown fox", 10), vec!["the quick", "brown fox"]);
+        assert_eq!(split_text("abcde fghij", 5), vec!["abcde", "fghij"]);
+    }
+
+    #[test]
+    fn split_text_cuts_words_longer_than_a_piece() {
+        assert_eq!(split_text("abcdefghij klm", 4), vec!["abcd", "efgh", "ij", "klm"]);
+    }
+
+    #[test]
+    fn split_text_pieces_always_fit() {
+        let text = "lorem ipsum dolor sit amet, consectetur adipiscing elit, sed do eiusmod tempor incididunt ut labore";
+
+        for max_len in 1..20 {
+            let pieces = split_text(text, max_len);
+
+            assert!(pieces.iter().all(|piece| !piece.is_empty() && piece.len() <= max_len));
+            assert_eq!(pieces.join(" ").split_whitespace().collect::<String>(), text.split_whitespace().collect::<String>());
+        }
+    }
+}
@@ -0,0 +1,31 @@
+use std::io::BufRead;
+
+use crossbeam::channel::{unbounded, Receiver};
+
+use crate::{
+    app_state::AppState,
+    commands::{run_command, CommandContext},
+};
+
+// Reads lines from stdin on its own thread, the main loop picks them up with `try_recv`.
+pub fn start() -> Receiver<String> {
+    let (tx, rx) = unbounded();
+
+    std::thread::spawn(move || {
+        for line in std::io::stdin().lock().lines().map_while(Result::ok) {
+            if tx.send(line).is_err() {
+                break;
+            }
+        }
+    });
+
+    rx
+}
+
+// The console has full admin rights and the slash is optional.
+pub fn run_console_command(line: &str, state: &AppState) {
+    let line = line.trim();
+    let line = line.strip_prefix('/').unwrap_or(line);
+
+    run_command(&mut CommandContext { client: None, state, output: None }, line);
+}
@@ -0,0 +1,119 @@
+use crate::{app_state::AppState, connection::ClientConnection, corporations::REQUISITION_CATALOG, packets::Team, stocks::CORPORATIONS};
+
+// What /corp and the corporation menus do. We don't know which menu buttons the client sends for which entry, so the
+// menus only show these lists and picking an entry goes through /corp. Entries are numbered from 1.
+
+pub fn apply(state: &AppState, account_id: u32, team: Option<Team>) -> String {
+    let Some(team) = team.filter(|team| CORPORATIONS.contains(team)) else {
+        return "Apply with /corp apply <goldmen|monsota|oxs|nexaco|pentacom>.".to_string();
+    };
+
+    if !state.corporations.apply(team, account_id) {
+        "You already work for a corporation.".to_string()
+    } else if state.corporations.is_manager(account_id) {
+        format!("You are now the manager of {team:?}.")
+    } else {
+        format!("You applied to {team:?}, a manager has to hire you.")
+    }
+}
+
+pub fn hire(state: &AppState, account_id: u32, pick: Option<usize>) -> String {
+    let Some(team) = state.corporations.managed_by(account_id) else {
+        return "Only managers can hire.".to_string();
+    };
+
+    let applications = state.corporations.corporations.get(&team).map(|corp| corp.applications.clone()).unwrap_or_default();
+
+    let Some(applicant) = pick.and_then(|index| index.checked_sub(1)).and_then(|index| applications.get(index)) else {
+        if applications.is_empty() {
+            return "Nobody has applied.".to_string();
+        }
+
+        return format!("Applications: {}. Hire with /corp hire <number>.", numbered_names(state, &applications));
+    };
+
+    if !state.corporations.hire(team, *applicant) {
+        return "They're not applying any more.".to_string();
+    }
+
+    format!("Hired {}.", state.player_store.lock().unwrap().account_name(*applicant))
+}
+
+// Managers can't fire themselves, so they aren't listed.
+pub fn fire(state: &AppState, account_id: u32, pick: Option<usize>) -> String {
+    let Some(team) = state.corporations.managed_by(account_id) else {
+        return "Only managers can fire.".to_string();
+    };
+
+    let employees = state
+        .corporations
+        .corporations
+        .get(&team)
+        .map(|corp| corp.employees.iter().copied().filter(|id| *id != account_id).collect::<Vec<u32>>())
+        .unwrap_or_default();
+
+    let Some(employee) = pick.and_then(|index| index.checked_sub(1)).and_then(|index| employees.get(index)) else {
+        if employees.is_empty() {
+            return "You have no employees.".to_string();
+        }
+
+        return format!("Employees: {}. Fire with /corp fire <number>.", numbered_names(state, &employees));
+    };
+
+    if !state.corporations.fire(team, *employee) {
+        return "They don't work for you any more.".to_string();
+    }
+
+    format!("Fired {}.", state.player_store.lock().unwrap().account_name(*employee))
+}
+
+pub fn team(state: &AppState, account_id: u32) -> String {
+    let Some(corp) = state.corporations.employer_of(account_id).and_then(|team| state.corporations.corporations.get(&team).map(|corp| corp.clone())) else {
+        return "You don't work for a corporation.".to_string();
+    };
+
+    let data = state.player_store.lock().unwrap();
+
+    let manager = corp.manager.map_or("Nobody".to_string(), |id| data.account_name(id));
+    let employees = corp.employees.iter().filter(|id| Some(**id) != corp.manager).map(|id| data.account_name(*id)).collect::<Vec<String>>();
+
+    format!("{:?} - Manager: {manager}, Employees: {}, Budget: ${}", corp.team, employees.join(", "), corp.budget)
+}
+
+// What it costs depends on who asks for it.
+pub fn requisition(state: &AppState, connection: &ClientConnection, pick: Option<usize>) -> String {
+    let Some(team) = state.corporations.employer_of(connection.account_id) else {
+        return "You don't work for a corporation.".to_string();
+    };
+
+    let Some((item_type, cost, name)) = pick.and_then(|index| index.checked_sub(1)).and_then(|index| REQUISITION_CATALOG.get(index)) else {
+        let entries = REQUISITION_CATALOG
+            .iter()
+            .enumerate()
+            .map(|(i, (_, cost, name))| format!("{}. {name} ${}", i + 1, connection.store_price(*cost)))
+            .collect::<Vec<String>>();
+
+        return format!("{}. Requisition with /corp requisition <number>.", entries.join(", "));
+    };
+
+    let cost = connection.store_price(*cost);
+
+    if !state.corporations.spend(team, cost) {
+        return format!("{team:?} can't afford a {name}.");
+    }
+
+    if state.items.spawn(*item_type, connection.camera_pos, state.network_tick()).is_none() {
+        // Nowhere to put it, so give the money back.
+        state.corporations.deposit(team, cost);
+
+        return "There's no room for any more items, try again later.".to_string();
+    }
+
+    format!("Requisitioned a {name} for ${cost}.")
+}
+
+fn numbered_names(state: &AppState, account_ids: &[u32]) -> String {
+    let data = state.player_store.lock().unwrap();
+
+    account_ids.iter().enumerate().map(|(i, id)| format!("{}. {}", i + 1, data.account_name(*id))).collect::<Vec<String>>().join(", ")
+}
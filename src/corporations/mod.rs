@@ -0,0 +1,170 @@
+pub mod actions;
+
+use dashmap::DashMap;
+use serde::{Deserialize, Serialize};
+
+use crate::{packets::Team, stocks::CORPORATIONS, storage::PlayerStore};
+
+// Where the SRK backend keeps corporations, other backends import it the first time they start.
+pub const CORPORATIONS_FILE: &str = "corporations.json";
+
+pub const STARTING_CORPORATION_BUDGET: i32 = 10_000;
+
+// (item type, base cost, name) of everything a corporation can requisition for its employees. The cost goes through
+// store_price for whoever asks.
+pub const REQUISITION_CATALOG: [(u32, i32, &str); 4] = [(0, 1200, "AK-47"), (2, 1200, "M-16"), (6, 800, "Uzi"), (8, 400, "9mm")];
+
+#[derive(Default)]
+pub struct CorporationManager {
+    pub corporations: DashMap<Team, Corporation>,
+}
+
+// Everything in here is keyed by account id, so employment survives reconnecting.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Corporation {
+    pub team: Team,
+    pub manager: Option<u32>,
+    pub employees: Vec<u32>,
+    pub applications: Vec<u32>,
+    pub budget: i32,
+}
+
+impl CorporationManager {
+    pub fn new() -> Self {
+        let corporations = DashMap::new();
+
+        for team in CORPORATIONS {
+            corporations.insert(
+                team,
+                Corporation {
+                    team,
+                    manager: None,
+                    employees: vec![],
+                    applications: vec![],
+                    budget: STARTING_CORPORATION_BUDGET,
+                },
+            );
+        }
+
+        Self { corporations }
+    }
+
+    // Corporations the store has never seen start out empty.
+    pub fn load(data: &dyn PlayerStore) -> Self {
+        let manager = Self::new();
+
+        for corp in data.load_corporations() {
+            if manager.corporations.contains_key(&corp.team) {
+                manager.corporations.insert(corp.team, corp);
+            }
+        }
+
+        manager
+    }
+
+    pub fn save(&self, data: &mut dyn PlayerStore) {
+        let mut corporations = self.corporations.iter().map(|corp| corp.clone()).collect::<Vec<Corporation>>();
+        corporations.sort_by_key(|corp| corp.team as u8);
+
+        data.save_corporations(&corporations);
+    }
+
+    pub fn employer_of(&self, account_id: u32) -> Option<Team> {
+        self.corporations.iter().find(|corp| corp.employees.contains(&account_id)).map(|corp| corp.team)
+    }
+
+    pub fn is_manager(&self, account_id: u32) -> bool {
+        self.managed_by(account_id).is_some()
+    }
+
+    pub fn managed_by(&self, account_id: u32) -> Option<Team> {
+        self.corporations.iter().find(|corp| corp.manager == Some(account_id)).map(|corp| corp.team)
+    }
+
+    // Applying to a corporation without a manager makes you its manager straight away.
+    pub fn apply(&self, team: Team, account_id: u32) -> bool {
+        if self.employer_of(account_id).is_some() {
+            return false;
+        }
+
+        for mut corp in self.corporations.iter_mut() {
+            corp.applications.retain(|id| *id != account_id);
+        }
+
+        let Some(mut corp) = self.corporations.get_mut(&team) else {
+            return false;
+        };
+
+        if corp.manager.is_none() {
+            corp.manager = Some(account_id);
+            corp.employees.push(account_id);
+        } else {
+            corp.applications.push(account_id);
+        }
+
+        true
+    }
+
+    pub fn hire(&self, team: Team, account_id: u32) -> bool {
+        let Some(mut corp) = self.corporations.get_mut(&team) else {
+            return false;
+        };
+
+        if !corp.applications.contains(&account_id) {
+            return false;
+        }
+
+        corp.applications.retain(|id| *id != account_id);
+        corp.employees.push(account_id);
+
+        true
+    }
+
+    // Managers can't fire themselves, they have to quit.
+    pub fn fire(&self, team: Team, account_id: u32) -> bool {
+        let Some(mut corp) = self.corporations.get_mut(&team) else {
+            return false;
+        };
+
+        if corp.manager == Some(account_id) || !corp.employees.contains(&account_id) {
+            return false;
+        }
+
+        corp.employees.retain(|id| *id != account_id);
+
+        true
+    }
+
+    pub fn quit(&self, account_id: u32) {
+        for mut corp in self.corporations.iter_mut() {
+            corp.employees.retain(|id| *id != account_id);
+            corp.applications.retain(|id| *id != account_id);
+
+            if corp.manager == Some(account_id) {
+                corp.manager = corp.employees.first().copied();
+            }
+        }
+    }
+
+    pub fn budget(&self, team: Team) -> i32 {
+        self.corporations.get(&team).map_or(0, |corp| corp.budget)
+    }
+
+    pub fn deposit(&self, team: Team, amount: i32) {
+        if let Some(mut corp) = self.corporations.get_mut(&team) {
+            corp.budget = corp.budget.saturating_add(amount);
+        }
+    }
+
+    pub fn spend(&self, team: Team, amount: i32) -> bool {
+        if let Some(mut corp) = self.corporations.get_mut(&team)
+            && corp.budget >= amount
+        {
+            corp.budget -= amount;
+
+            return true;
+        }
+
+        false
+    }
+}
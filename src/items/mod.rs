@@ -1,6 +1,13 @@
 use dashmap::DashMap;
 
-use crate::{connection::packets::buf_writer::AlexBufWriter, world::{quaternion::Quaternion, vector::Vector}};
+use crate::{connection::packets::buf_writer::AlexBufWriter, world::{quaternion::Quaternion, vector::Vector}, TICKS_PER_SECOND};
+
+// Ids below 64 are reserved for the item following each client. encode_obj only has 8 bits for the id.
+pub const FIRST_SPAWNED_ITEM_ID: u32 = 64;
+pub const MAX_ITEM_ID: u32 = 255;
+
+// How long a spawned item stays in the world.
+pub const ITEM_LIFETIME: i32 = TICKS_PER_SECOND * 60 * 5;
 
 #[derive(Default)]
 pub struct ItemManager {
@@ -11,7 +18,9 @@ pub struct Item {
     pub item_type: u32,
     pub item_id: u32,
     pub pos: Vector,
-    pub rot: Quaternion
+    pub rot: Quaternion,
+    // The network tick it goes away on, None if it stays until something removes it.
+    pub despawn_tick: Option<i32>,
 }
 
 impl Item {
@@ -46,4 +55,26 @@ impl ItemManager {
             items: DashMap::new()
         }
     }
+
+    // Spawned items go away after ITEM_LIFETIME, so the ids get used again.
+    pub fn spawn(&self, item_type: u32, pos: Vector, network_tick: i32) -> Option<u32> {
+        let item_id = (FIRST_SPAWNED_ITEM_ID..=MAX_ITEM_ID).find(|id| !self.items.contains_key(id))?;
+
+        self.items.insert(
+            item_id,
+            Item {
+                item_type,
+                item_id,
+                pos,
+                rot: Quaternion::zero(),
+                despawn_tick: Some(network_tick + ITEM_LIFETIME),
+            },
+        );
+
+        Some(item_id)
+    }
+
+    pub fn despawn_expired(&self, network_tick: i32) {
+        self.items.retain(|_, item| item.despawn_tick.is_none_or(|tick| tick > network_tick));
+    }
 }
\ No newline at end of file
@@ -0,0 +1,273 @@
+use std::{
+    collections::{HashMap, hash_map::Entry},
+    fmt::Display,
+    fs::{File, OpenOptions},
+    io::Write,
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::{LazyLock, Mutex, RwLock},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender};
+
+use crate::config::config_main::ConfigMain;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Level {
+    Error,
+    Warn,
+    Info,
+    Debug,
+    Trace,
+}
+
+impl Display for Level {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Level::Error => write!(f, "ERROR"),
+            Level::Warn => write!(f, "WARN"),
+            Level::Info => write!(f, "INFO"),
+            Level::Debug => write!(f, "DEBUG"),
+            Level::Trace => write!(f, "TRACE"),
+        }
+    }
+}
+
+impl FromStr for Level {
+    type Err = ();
+
+    fn from_str(level: &str) -> Result<Self, Self::Err> {
+        match level.trim().to_lowercase().as_str() {
+            "error" => Ok(Level::Error),
+            "warn" => Ok(Level::Warn),
+            "info" => Ok(Level::Info),
+            "debug" => Ok(Level::Debug),
+            "trace" => Ok(Level::Trace),
+            _ => Err(()),
+        }
+    }
+}
+
+// The separate logs next to the main server log.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LogFile {
+    Server,
+    Chat,
+    Audit,
+}
+
+impl LogFile {
+    fn file_name(&self) -> &'static str {
+        match self {
+            LogFile::Server => "server.log",
+            LogFile::Chat => "chat.log",
+            LogFile::Audit => "audit.log",
+        }
+    }
+}
+
+struct Filter {
+    level: Level,
+    targets: HashMap<String, Level>,
+}
+
+struct OpenLog {
+    file: File,
+    size: u64,
+}
+
+struct Files {
+    dir: Option<PathBuf>,
+    max_size: u64,
+    keep: u32,
+    open: HashMap<LogFile, OpenLog>,
+}
+
+static FILTER: LazyLock<RwLock<Filter>> = LazyLock::new(|| RwLock::new(Filter { level: Level::Info, targets: HashMap::new() }));
+static FILES: LazyLock<Mutex<Files>> = LazyLock::new(|| Mutex::new(Files { dir: None, max_size: 0, keep: 0, open: HashMap::new() }));
+
+// Everyone following the log, like RCON clients with `log on`.
+static SUBSCRIBERS: Mutex<Vec<UnboundedSender<String>>> = Mutex::new(Vec::new());
+
+// "srk=debug,packets=trace" into per target levels, anything that doesn't parse is skipped.
+pub fn parse_target_levels(levels: &str) -> HashMap<String, Level> {
+    levels
+        .split(',')
+        .filter_map(|entry| entry.split_once('='))
+        .filter_map(|(target, level)| Some((target.trim().to_lowercase(), level.parse().ok()?)))
+        .collect()
+}
+
+// Applies the log settings from config.txt, again on every reload.
+pub fn configure(config: &ConfigMain) {
+    {
+        let mut filter = FILTER.write().unwrap();
+        filter.level = config.log_level.parse().unwrap_or(Level::Info);
+        filter.targets = parse_target_levels(&config.log_levels);
+    }
+
+    let mut files = FILES.lock().unwrap();
+    let dir = (!config.log_dir.is_empty()).then(|| PathBuf::from(&config.log_dir));
+
+    if dir != files.dir {
+        files.open.clear();
+    }
+
+    if let Some(dir) = &dir
+        && let Err(err) = std::fs::create_dir_all(dir)
+    {
+        println!("Failed to create the log directory {}: {err}", dir.display());
+    }
+
+    files.dir = dir;
+    files.max_size = config.log_max_size as u64 * 1024;
+    files.keep = config.log_files;
+}
+
+pub fn enabled(level: Level, target: &str) -> bool {
+    let filter = FILTER.read().unwrap();
+
+    level <= *filter.targets.get(target).unwrap_or(&filter.level)
+}
+
+// Days since 1970 to a calendar date, from Howard Hinnant's civil_from_days.
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+
+    (yoe + era * 400 + (month <= 2) as i64, month, day)
+}
+
+// UTC, so logs from different machines line up.
+pub fn timestamp() -> String {
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |time| time.as_secs()) as i64;
+    let (year, month, day) = civil_from_days(now.div_euclid(86_400));
+    let seconds = now.rem_euclid(86_400);
+
+    format!("{year:04}-{month:02}-{day:02} {:02}:{:02}:{:02}", seconds / 3600, seconds / 60 % 60, seconds % 60)
+}
+
+fn rotate(dir: &Path, name: &str, keep: u32) {
+    for index in (1..keep).rev() {
+        let _ = std::fs::rename(dir.join(format!("{name}.{index}")), dir.join(format!("{name}.{}", index + 1)));
+    }
+
+    if keep > 0 {
+        let _ = std::fs::rename(dir.join(name), dir.join(format!("{name}.1")));
+    } else {
+        let _ = std::fs::remove_file(dir.join(name));
+    }
+}
+
+fn append(log: LogFile, line: &str) {
+    let mut files = FILES.lock().unwrap();
+
+    let Some(dir) = files.dir.clone() else {
+        return;
+    };
+
+    let (max_size, keep) = (files.max_size, files.keep);
+    let name = log.file_name();
+
+    // Start a fresh file once this one is full.
+    if max_size > 0 && files.open.get(&log).is_some_and(|open| open.size >= max_size) {
+        files.open.remove(&log);
+        rotate(&dir, name, keep);
+    }
+
+    let open = match files.open.entry(log) {
+        Entry::Occupied(entry) => entry.into_mut(),
+        Entry::Vacant(entry) => {
+            let path = dir.join(name);
+
+            match OpenOptions::new().create(true).append(true).open(&path) {
+                Ok(file) => {
+                    let size = file.metadata().map_or(0, |meta| meta.len());
+                    entry.insert(OpenLog { file, size })
+                }
+                Err(err) => {
+                    println!("Failed to open {}: {err}", path.display());
+
+                    return;
+                }
+            }
+        }
+    };
+
+    if writeln!(open.file, "{line}").is_ok() {
+        open.size += line.len() as u64 + 1;
+    }
+}
+
+// Prints a line, writes it to the server log and hands it to every subscriber.
+pub fn write(level: Level, target: &str, message: String) {
+    if !enabled(level, target) {
+        return;
+    }
+
+    let line = format!("{} {level:<5} [{target}] {message}", timestamp());
+
+    println!("{line}");
+    append(LogFile::Server, &line);
+
+    SUBSCRIBERS.lock().unwrap().retain(|subscriber| subscriber.send(line.clone()).is_ok());
+}
+
+// Chat and admin actions also go to their own files, whatever the log level is.
+pub fn write_to(log: LogFile, target: &str, message: String) {
+    append(log, &format!("{} {message}", timestamp()));
+
+    write(Level::Info, target, message);
+}
+
+pub fn subscribe() -> UnboundedReceiver<String> {
+    let (tx, rx) = unbounded_channel();
+
+    SUBSCRIBERS.lock().unwrap().push(tx);
+
+    rx
+}
+
+// Each takes a target (the subsystem) and then format! arguments, `info!("srk", "Saved {} players", count)`.
+macro_rules! log {
+    ($level:expr, $target:expr, $($arg:tt)*) => {
+        if $crate::logging::enabled($level, $target) {
+            $crate::logging::write($level, $target, format!($($arg)*))
+        }
+    };
+}
+
+macro_rules! error {
+    ($target:expr, $($arg:tt)*) => { log!($crate::logging::Level::Error, $target, $($arg)*) };
+}
+
+macro_rules! warn {
+    ($target:expr, $($arg:tt)*) => { log!($crate::logging::Level::Warn, $target, $($arg)*) };
+}
+
+macro_rules! info {
+    ($target:expr, $($arg:tt)*) => { log!($crate::logging::Level::Info, $target, $($arg)*) };
+}
+
+macro_rules! debug {
+    ($target:expr, $($arg:tt)*) => { log!($crate::logging::Level::Debug, $target, $($arg)*) };
+}
+
+macro_rules! trace {
+    ($target:expr, $($arg:tt)*) => { log!($crate::logging::Level::Trace, $target, $($arg)*) };
+}
+
+macro_rules! chat_log {
+    ($($arg:tt)*) => { $crate::logging::write_to($crate::logging::LogFile::Chat, "chat", format!($($arg)*)) };
+}
+
+macro_rules! audit_log {
+    ($($arg:tt)*) => { $crate::logging::write_to($crate::logging::LogFile::Audit, "audit", format!($($arg)*)) };
+}
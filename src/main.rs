@@ -3,18 +3,16 @@
 
 use std::{
     net::SocketAddr,
-    sync::{Arc, Mutex, RwLock},
-    time::SystemTime,
+    sync::{atomic::AtomicBool, Arc, Mutex, RwLock},
+    time::{Instant, SystemTime},
 };
 
 use crate::{
-    app_state::{AppState, ChatType, GameManager}, config::config_main::ConfigMain, connection::{
-        events::{event_types::{update_vehicle_type_color::EventUpdateVehicleTypeColor, Event}, 
-            EventManager}
-        , packets::{self}, ClientConnection
-    }, items::ItemManager, map::Map, masterserver::MasterServer, packets::{
+    access::{bans::BanManager, permissions::{role_for_account, LoginAttempts}, whitelist::WhitelistManager}, app_state::{AppState, ChatType, GameManager}, chat::ChatManager, config::config_main::ConfigMain, corporations::CorporationManager, connection::{
+        events::EventManager, packets::{self}, ClientConnection
+    }, items::ItemManager, map::Map, masterserver::MasterServer, metrics::Metrics, packets::{
         clientbound::{initial_sync::ClientboundInitialSyncPacket, kick::ClientboundKickPacket, server_info::ServerInfo}, Encodable, PacketType
-    }, scheduler::TaskScheduler, srk_parser::SrkData, vehicles::{Vehicle, VehicleManager}, voice::VoiceManager, world::{quaternion::Quaternion, transform::Transform, vector::Vector}
+    }, scheduler::TaskScheduler, stocks::StockManager, storage::open_player_store, vehicles::{Vehicle, VehicleManager}, voice::VoiceManager, world::{quaternion::Quaternion, transform::Transform, vector::Vector}
 };
 use crossbeam::channel::{Sender, unbounded};
 use dashmap::DashMap;
@@ -22,14 +20,26 @@ use tokio::net::UdpSocket;
 
 extern crate serde_repr;
 
+#[macro_use]
+pub mod logging;
+
+pub mod access;
 pub mod app_state;
+pub mod chat;
 pub mod commands;
 pub mod config;
+pub mod console;
+pub mod corporations;
 pub mod connection;
 pub mod items;
 pub mod masterserver;
+pub mod metrics;
+pub mod ratings;
+pub mod rcon;
 pub mod scheduler;
 pub mod srk_parser;
+pub mod stocks;
+pub mod storage;
 pub mod vehicles;
 pub mod voice;
 pub mod world;
@@ -45,13 +55,24 @@ pub struct Connection {
 
 #[tokio::main]
 async fn main() {
+    let args = std::env::args().collect::<Vec<String>>();
+
+    if args.get(1).is_some_and(|arg| arg == "srk") {
+        srk_parser::run_cli(&args[2..]);
+
+        return;
+    }
+
     let config = ConfigMain::read_from_file();
+    logging::configure(&config);
 
     let _city = Map::load();
 
     let mut masterserver = MasterServer::init(&config).await;
 
-    let srk_data = SrkData::read_from_file();
+    let player_store = open_player_store(&config);
+    let bans = BanManager::load(player_store.as_ref());
+    let corporations = CorporationManager::load(player_store.as_ref());
 
     let state = AppState {
         network_tick: RwLock::new(1),
@@ -59,12 +80,20 @@ async fn main() {
         map_name: RwLock::new("test2".to_string()),
         masterserver: masterserver.clone(),
         events: EventManager::new(),
+        chat: ChatManager::default(),
         voices: VoiceManager::new(),
         items: ItemManager::new(),
         vehicles: VehicleManager::new(),
+        stocks: StockManager::new(),
+        corporations,
         tasks: TaskScheduler::new(),
-        srk_data: Arc::new(Mutex::new(srk_data)),
-        config: config.clone(),
+        player_store: Mutex::new(player_store),
+        bans,
+        whitelist: WhitelistManager::read_from_file(config.whitelist),
+        logins: LoginAttempts::default(),
+        config: RwLock::new(config.clone()),
+        running: AtomicBool::new(true),
+        metrics: Arc::new(Metrics::default()),
         connections: DashMap::new(),
         auth_data: DashMap::new(),
         game_state: GameManager::default(),
@@ -80,35 +109,29 @@ async fn main() {
         velocity: Vector::zero()
     }));*/
 
-    state.events.emit_globally(Event::UpdateVehicleTypeColor(EventUpdateVehicleTypeColor {
-        tick_created: state.network_tick(),
-        vehicle_color: 1,
+    let car = Vehicle {
         vehicle_id: 0,
         vehicle_type: 0,
-    }));
+        color: 1,
+        engine_rpm: 1200,
+        transform: Transform::pos_rot(
+            Vector {
+                x: 1800.0,
+                y: 82.0,
+                z: 1500.0,
+            },
+            Quaternion::euler(0.0, 45.0, 0.0),
+        ),
+    };
 
-    state.vehicles.vehicles.insert(
-        0,
-        Vehicle {
-            vehicle_id: 0,
-            engine_rpm: 1200,
-            transform: Transform::pos_rot(
-                Vector {
-                    x: 1800.0,
-                    y: 82.0,
-                    z: 1500.0,
-                },
-                Quaternion::euler(0.0, 45.0, 0.0),
-            ),
-        },
-    );
+    state.vehicles.vehicles.insert(0, car);
 
     let socket = UdpSocket::bind(format!("0.0.0.0:{}", config.port)).await.expect("Failed to bind socket");
     let recv_sock = Arc::new(socket);
 
-    println!("[SERVER] Listening on {}", recv_sock.local_addr().unwrap());
+    info!("server", "Listening on {}", recv_sock.local_addr().unwrap());
 
-    let send_sock = make_sender(recv_sock.clone());
+    let send_sock = make_sender(recv_sock.clone(), state.metrics.clone());
 
     masterserver.connect(send_sock.clone());
 
@@ -116,38 +139,79 @@ async fn main() {
         state.masterserver.send(vec![b'@']);
     }));
 
+    // Autosave everyones money, ratings and play time.
+    state.tasks.schedule_task(state.network_tick(), Some(TICKS_PER_SECOND * 60), Box::new(|state: &AppState| {
+        state.save_players();
+    }));
+
+    // Criminal ratings slowly wear off.
+    state.tasks.schedule_task(state.network_tick(), Some(TICKS_PER_SECOND * 60), Box::new(|state: &AppState| {
+        for mut connection in state.connections.iter_mut() {
+            connection.crim_rating = connection.crim_rating.saturating_sub(10);
+        }
+    }));
+
     state.tasks.schedule_task(state.network_tick(), Some(TICKS_PER_SECOND * 10), Box::new(|state: &AppState| {
         state.auth_data.retain(|_, (tick_created, _)| {
             state.network_tick() - *tick_created <= TICKS_PER_SECOND * 10
         });
     }));
 
+    // Clear out spawned items that have been lying around too long.
+    state.tasks.schedule_task(state.network_tick(), Some(TICKS_PER_SECOND), Box::new(|state: &AppState| {
+        state.items.despawn_expired(state.network_tick());
+    }));
+
+    // Refresh what the metrics endpoint serves.
+    state.tasks.schedule_task(state.network_tick(), Some(TICKS_PER_SECOND), Box::new(|state: &AppState| {
+        metrics::update_snapshot(state);
+    }));
+
+    let console = console::start();
+    let mut rcon = rcon::start(&config);
+    metrics::start(&config, state.metrics.clone());
+
     let mut packet_buf = [0; 1024];
     let mut last_tick = SystemTime::now();
 
-    loop {
+    while state.running() {
+        while let Ok(line) = console.try_recv() {
+            console::run_console_command(&line, &state);
+        }
+
+        while let Ok(request) = rcon.try_recv() {
+            rcon::run_request(request, &state);
+        }
+
         // Recieve from our sockets, then decode the packet if it is successfull
         if let Ok((size, src)) = recv_sock.try_recv_from(&mut packet_buf)
             && let Some(packet_type) = packets::decode_packet(packet_buf[..size].to_vec().clone(), src, &state)
         {
-            // On our connection lets handle the packet
-            if let Some(mut connection) = state.connections.get_mut(&src) {
+            // On our connection lets handle the packet. It's handled on a copy and written back after, so we aren't
+            // holding our shard while commands go through every connection, ours included.
+            let connection = state.connections.get_mut(&src).map(|mut connection| {
                 connection.last_packet = SystemTime::now();
+                connection.protocol_errors = 0;
 
+                connection.clone()
+            });
+
+            if let Some(mut connection) = connection {
                 connection.handle_packet(packet_type.clone(), &state).await;
+
+                // Unless they were kicked while it ran.
+                if let Some(mut slot) = state.connections.get_mut(&src) {
+                    *slot = connection;
+                }
             }
 
             // If its a leave, handle it
             if let PacketType::ServerboundLeave = packet_type
-                && let Some(connection) = state.connections.get(&src)
+                && let Some((_, mut connection)) = state.connections.remove(&src)
             {
                 connection.handle_leave(&state);
 
-                println!("[SERVER] {} left.", connection.username);
-
-                drop(connection);
-
-                state.connections.remove(&src);
+                info!("server", "{} left.", connection.username);
             }
 
             // If its a serverbound info request, lets handle it ourselves so we know what were doing
@@ -169,18 +233,51 @@ async fn main() {
             {
                 let (_, auth_data) = auth_data.clone();
 
+                let ban = state.bans.check(state.player_store.lock().unwrap().as_ref(), auth_data.account_id, src.ip());
+
+                // Reconnects already have a slot, only new players need one.
+                let prev_src = state.get_connection_addr_by_rosa_id(auth_data.account_id);
+                let slot = if prev_src.is_none() && !state.connections.contains_key(&src) {
+                    Some(state.find_join_slot(auth_data.account_id))
+                } else {
+                    None
+                };
+
                 // If the password doesnt match what the client sent, lets just disconnect them.
-                if !state.config.server_password.is_empty() && request.password != state.config.server_password {
+                if !state.config().server_password.is_empty() && request.password != state.config().server_password {
                     let res = ClientboundKickPacket {
                         reason: "You sent an incorrect password, loser.".to_string(),
                     };
 
                     send_packet_to_socket(&send_sock, src, &state, &res).await;
 
+                // Banned, tell them how long for.
+                } else if let Some(reason) = ban {
+                    info!("server", "Rejected {} from {src}: {reason}", auth_data.name);
+
+                    send_packet_to_socket(&send_sock, src, &state, &ClientboundKickPacket { reason }).await;
+
+                // Private server and they aren't on the list.
+                } else if !state.whitelist.allows(&auth_data) {
+                    info!("server", "Rejected {} from {src}: not whitelisted", auth_data.name);
+
+                    let res = ClientboundKickPacket {
+                        reason: "This server is whitelisted.".to_string(),
+                    };
+
+                    send_packet_to_socket(&send_sock, src, &state, &res).await;
+
+                // No room for them.
+                } else if let Some(Err(reason)) = slot {
+                    info!("server", "Rejected {} from {src}: {reason}", auth_data.name);
+
+                    send_packet_to_socket(&send_sock, src, &state, &ClientboundKickPacket { reason }).await;
+
                 // Valid connection and password is correct.
                 } else {
-                    println!(
-                        "[SERVER] Got connection from {:?} with name {} and auth {} - Sending sync!",
+                    info!(
+                        "server",
+                        "Got connection from {:?} with name {} and auth {} - Sending sync!",
                         src, auth_data.name, request.auth_ticket
                     );
 
@@ -193,12 +290,19 @@ async fn main() {
                         versus_movedelay: None,
                     };
 
+                    // The SRK doesn't write a new account out on its own, the next save picks it up.
                     {
-                        let mut data = state.srk_data.lock().unwrap();
+                        let mut data = state.player_store.lock().unwrap();
+
+                        data.begin_batch();
                         data.create_account(&auth_data);
+                        data.add_stat(auth_data.account_id, state.config().gamemode, "joins", 1);
+                        data.end_batch();
                     }
 
-                    let prev_src = state.get_connection_addr_by_rosa_id(auth_data.account_id);
+                    // Set when a client starts over, its event counter is back at 0 and it needs a snapshot. A repeated join
+                    // request from a connection we already have isn't one, that client is still counting.
+                    let mut started = false;
 
                     // Socket deduping, if we have a socket with this account id, we reparent the old socket to the new one
                     // Thus kicking the OG client.
@@ -210,40 +314,62 @@ async fn main() {
 
                         state.reparent_connection(prev_src, src);
 
+                        if let Some(connection) = state.connections.get(&src) {
+                            state.events.add_player(connection.client_id);
+                            started = true;
+                        }
+
                     // If we have a connection already, then lets just make the client happy and send the initial sync.
                     } else if let Some(connection) = state.connections.get(&src) {
                         connection.send_data(res.encode(&state));
 
                     // Lets make a new connection from the auth packet, send initial sync and stuff.
-                    } else {
-                        let connection = ClientConnection::from_auth(src, send_sock.clone(), &auth_data, state.find_empty_slot_id());
+                    } else if let Some(Ok(client_id)) = slot {
+                        let mut connection = ClientConnection::from_auth(src, send_sock.clone(), &auth_data, client_id, state.network_tick());
+                        {
+                            let data = state.player_store.lock().unwrap();
 
-                        connection.send_data(res.encode(&state));
+                            connection.role = role_for_account(&state.config(), data.as_ref(), auth_data.account_id);
 
-                        connection.handle_join(&state);
+                            if let Some(account) = data.get_account(auth_data.account_id) {
+                                connection.load_account(&account);
+                            }
+                        }
 
-                        state.send_chat(
-                            ChatType::PrivateMessage,
-                            "This server is NOT real, and you WILL NOT get into a game.",
-                            connection.client_id as i32,
-                            0,
-                        );
+                        connection.send_data(res.encode(&state));
 
                         state.connections.insert(src, connection);
+
+                        if let Some(connection) = state.connections.get(&src) {
+                            connection.handle_join(&state);
+
+                            state.send_chat(
+                                ChatType::PrivateMessage,
+                                "This server is NOT real, and you WILL NOT get into a game.",
+                                connection.client_id as i32,
+                                0,
+                            );
+                        }
+
+                        started = true;
                     }
-                }
 
-                if let Some(connection) = state.connections.get(&src) {
-                    connection.update_money(&state);
-                    connection.update_player(&state);
+                    // They only need what things are like now, not everything that happened before they got here.
+                    if started && let Some(connection) = state.connections.get(&src) {
+                        state.send_snapshot(&[connection.client_id]);
+
+                        connection.update_money(&state);
+                        connection.update_player(&state);
+                    }
                 }
             }
 
             // When the MS sends us an auth packet, add the player to our auth stash so we can 
             // figure out who they are on join
             if let PacketType::MasterServerAuthPacket(ref auth) = packet_type {
-                println!(
-                    "[MasterServer] Recieved authentication packet for {} with phone #{} - Auth ticket: {}",
+                debug!(
+                    "masterserver",
+                    "Recieved authentication packet for {} with phone #{} - Auth ticket: {}",
                     auth.name, auth.phone_number, auth.auth_ticket
                 );
 
@@ -252,6 +378,17 @@ async fn main() {
         };
 
         if last_tick.elapsed().unwrap().as_millis() > 16 {
+            let tick_start = Instant::now();
+
+            // Drop events everyone has, anyone who fell too far behind skipped some so gets everything again.
+            let resynced = state.events.compact();
+
+            if !resynced.is_empty() {
+                debug!("server", "Clients {resynced:?} fell behind the event log, sending them a snapshot.");
+
+                state.send_snapshot(&resynced);
+            }
+
             // Start building game packets so we can send them to players
             for connection in state.connections.iter() {
                 connection.send_game_packet(&state);
@@ -265,7 +402,7 @@ async fn main() {
                 if connection.last_packet.elapsed().unwrap().as_millis() > (10 * 1000) {
                     connection.handle_leave(&state);
 
-                    println!("[SERVER] {} on address {} disconnected.", connection.username, connection.address);
+                    info!("server", "{} on address {} disconnected.", connection.username, connection.address);
 
                     false
                 } else {
@@ -280,11 +417,30 @@ async fn main() {
             let mut network_tick = state.network_tick.write().unwrap();
             *network_tick += 1;
 
+            state.metrics.record_tick(tick_start.elapsed());
+
             last_tick = SystemTime::now();
         }
     }
+
+    info!("server", "Shutting down...");
+
+    let clients = state.connections.iter().map(|conn| conn.client_id).collect::<Vec<u32>>();
+
+    for client_id in clients {
+        state.kick(client_id, "The server is shutting down.");
     }
 
+    state.corporations.save(state.player_store.lock().unwrap().as_mut());
+    state.player_store.lock().unwrap().close();
+    state.whitelist.save();
+
+    // Give the send task a moment to get the kicks out.
+    std::thread::sleep(std::time::Duration::from_millis(250));
+
+    info!("server", "Bye!");
+}
+
 pub async fn send_packet_to_socket(socket: &Sender<(Vec<u8>, SocketAddr)>, address: SocketAddr, state: &AppState, packet: &dyn Encodable) {
     let encoded_packet = packet.encode(state);
 
@@ -296,16 +452,15 @@ pub async fn send_packet_to_socket(socket: &Sender<(Vec<u8>, SocketAddr)>, addre
     socket.send((data, address)).expect("Failed to send packet to channel");
 }
 
-fn make_sender(send_sock: Arc<UdpSocket>) -> Sender<(Vec<u8>, SocketAddr)> {
+fn make_sender(send_sock: Arc<UdpSocket>, metrics: Arc<Metrics>) -> Sender<(Vec<u8>, SocketAddr)> {
     let (tx, rx) = unbounded::<(Vec<u8>, SocketAddr)>();
 
     tokio::spawn(async move {
-        loop {
-            let data = rx.recv();
+        // Ends once the server shuts down and drops every sender.
+        while let Ok(data) = rx.recv() {
+            metrics.record_out(data.1, data.0.len());
 
-            if let Ok(data) = data {
-                let _res = send_sock.send_to(&data.0, data.1).await;
-            }
+            let _res = send_sock.send_to(&data.0, data.1).await;
         }
     });
 
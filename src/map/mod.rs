@@ -12,13 +12,13 @@ pub struct Map {
 
 impl Map {
     pub fn load() -> Self {
-        println!("[LOADER] Loading the map!");
+        info!("map", "Loading the map!");
         let city_name = "test2".to_string();
         let start_time = SystemTime::now();
 
-        println!("[LOADER] Loading {city_name}.csx");
+        info!("map", "Loading {city_name}.csx");
         let city_file = CityFileCSX::load(&city_name);
-        println!("[LOADER] {city_name}.csx successfully loaded in {}ms", start_time.elapsed().unwrap().as_millis());
+        info!("map", "{city_name}.csx successfully loaded in {}ms", start_time.elapsed().unwrap().as_millis());
 
         let mut buildings = vec![];
         let mut blocks = vec![];
@@ -31,7 +31,7 @@ impl Map {
             }
         }
 
-        println!("[LOADER] Map loaded and parsed in {}ms", start_time.elapsed().unwrap().as_millis());
+        info!("map", "Map loaded and parsed in {}ms", start_time.elapsed().unwrap().as_millis());
 
         Self {
             city_file,
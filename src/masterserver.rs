@@ -12,7 +12,7 @@ pub struct MasterServer {
 
 impl MasterServer {
     pub async fn init(config: &ConfigMain) -> Self {
-        println!("[MasterServer] Initializing MasterServer... Attempting to connect to {}...", config.master_server_url);
+        info!("masterserver", "Initializing MasterServer... Attempting to connect to {}...", config.master_server_url);
 
         let mut host = if config.master_server_url.ends_with('/') {
             format!("{}anewzero/serverinfo.php", config.master_server_url)
@@ -48,7 +48,7 @@ impl MasterServer {
 
         let sock_addr = SocketAddr::from_str(&address);
 
-        println!("[MasterServer] MasterServer initialized... Waiting for connection...");
+        info!("masterserver", "MasterServer initialized... Waiting for connection...");
 
         MasterServer {
             address: sock_addr.unwrap(),
@@ -66,7 +66,7 @@ impl MasterServer {
                 .await
                 .expect("Failed to connect to server");
 
-            println!("[MasterServer] Connected to MasterServer! - Now online!");
+            info!("masterserver", "Connected to MasterServer! - Now online!");
 
             self.server_socket = Some(Arc::new(socket));
         }
@@ -74,7 +74,7 @@ impl MasterServer {
 
     pub fn connect(&mut self, tx: Sender<(Vec<u8>, SocketAddr)>) {
         self.server_socket = Some(tx);
-        println!("[MasterServer] Connected to MasterServer! - Now online!");
+        info!("masterserver", "Connected to MasterServer! - Now online!");
     }
 
     pub fn send(&self, data: Vec<u8>) {
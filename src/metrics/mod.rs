@@ -0,0 +1,275 @@
+use std::{
+    fmt::Write as _,
+    io::{Read, Write},
+    net::{SocketAddr, TcpListener, TcpStream},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, RwLock,
+    },
+    time::{Duration, Instant},
+};
+
+use dashmap::DashMap;
+use serde::Serialize;
+
+use crate::{
+    app_state::AppState,
+    config::config_main::ConfigMain,
+    packets::{GameMode, GameState},
+};
+
+#[derive(Debug, Clone, Copy, Default, Serialize)]
+pub struct Traffic {
+    pub packets_in: u64,
+    pub packets_out: u64,
+    pub bytes_in: u64,
+    pub bytes_out: u64,
+}
+
+// Counters shared with the send task and the HTTP thread, so everything in here is atomic.
+#[derive(Default)]
+pub struct Metrics {
+    pub packets_in: AtomicU64,
+    pub packets_out: AtomicU64,
+    pub bytes_in: AtomicU64,
+    pub bytes_out: AtomicU64,
+    pub tick_micros: AtomicU64,
+    pub clients: DashMap<SocketAddr, Traffic>,
+    pub snapshot: RwLock<MetricsSnapshot>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PlayerMetrics {
+    pub client_id: u32,
+    pub name: String,
+    pub account_id: u32,
+    pub address: String,
+    pub traffic: Traffic,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MetricsSnapshot {
+    pub server_name: String,
+    pub gamemode: GameMode,
+    pub game_state: GameState,
+    pub map: String,
+    pub round_number: u32,
+    pub network_tick: i32,
+    pub tick_duration_ms: f64,
+    pub global_events: u32,
+    pub max_players: u8,
+    pub players: Vec<PlayerMetrics>,
+    pub totals: Traffic,
+    pub per_second: Traffic,
+    #[serde(skip)]
+    pub taken_at: Instant,
+}
+
+impl Default for MetricsSnapshot {
+    fn default() -> Self {
+        Self {
+            server_name: String::new(),
+            gamemode: GameMode::Round,
+            game_state: GameState::default(),
+            map: String::new(),
+            round_number: 0,
+            network_tick: 0,
+            tick_duration_ms: 0.0,
+            global_events: 0,
+            max_players: 0,
+            players: vec![],
+            totals: Traffic::default(),
+            per_second: Traffic::default(),
+            taken_at: Instant::now(),
+        }
+    }
+}
+
+impl Metrics {
+    pub fn record_in(&self, address: SocketAddr, bytes: usize) {
+        self.packets_in.fetch_add(1, Ordering::Relaxed);
+        self.bytes_in.fetch_add(bytes as u64, Ordering::Relaxed);
+
+        let mut traffic = self.clients.entry(address).or_default();
+        traffic.packets_in += 1;
+        traffic.bytes_in += bytes as u64;
+    }
+
+    pub fn record_out(&self, address: SocketAddr, bytes: usize) {
+        self.packets_out.fetch_add(1, Ordering::Relaxed);
+        self.bytes_out.fetch_add(bytes as u64, Ordering::Relaxed);
+
+        let mut traffic = self.clients.entry(address).or_default();
+        traffic.packets_out += 1;
+        traffic.bytes_out += bytes as u64;
+    }
+
+    pub fn record_tick(&self, duration: Duration) {
+        self.tick_micros.store(duration.as_micros() as u64, Ordering::Relaxed);
+    }
+
+    fn totals(&self) -> Traffic {
+        Traffic {
+            packets_in: self.packets_in.load(Ordering::Relaxed),
+            packets_out: self.packets_out.load(Ordering::Relaxed),
+            bytes_in: self.bytes_in.load(Ordering::Relaxed),
+            bytes_out: self.bytes_out.load(Ordering::Relaxed),
+        }
+    }
+}
+
+// Run once a second from the game loop, the HTTP thread only ever reads the last snapshot.
+pub fn update_snapshot(state: &AppState) {
+    let metrics = &state.metrics;
+
+    // Forget anyone who isn't connected anymore.
+    metrics.clients.retain(|address, _| state.connections.contains_key(address));
+
+    let mut players = state
+        .connections
+        .iter()
+        .map(|conn| PlayerMetrics {
+            client_id: conn.client_id,
+            name: conn.username.clone(),
+            account_id: conn.account_id,
+            address: conn.address.to_string(),
+            traffic: metrics.clients.get(&conn.address).map(|traffic| *traffic).unwrap_or_default(),
+        })
+        .collect::<Vec<PlayerMetrics>>();
+    players.sort_by_key(|player| player.client_id);
+
+    let previous = metrics.snapshot.read().unwrap().clone();
+    let totals = metrics.totals();
+    let seconds = previous.taken_at.elapsed().as_secs_f64().max(0.001);
+    let rate = |now: u64, before: u64| (now.saturating_sub(before) as f64 / seconds).round() as u64;
+
+    let config = state.config();
+
+    let snapshot = MetricsSnapshot {
+        server_name: config.server_name.clone(),
+        gamemode: config.gamemode,
+        game_state: state.game_state(),
+        map: state.map_name(),
+        round_number: state.round_number(),
+        network_tick: state.network_tick(),
+        tick_duration_ms: metrics.tick_micros.load(Ordering::Relaxed) as f64 / 1000.0,
+        global_events: state.events.num_global_events(),
+        max_players: config.max_players,
+        players,
+        totals,
+        per_second: Traffic {
+            packets_in: rate(totals.packets_in, previous.totals.packets_in),
+            packets_out: rate(totals.packets_out, previous.totals.packets_out),
+            bytes_in: rate(totals.bytes_in, previous.totals.bytes_in),
+            bytes_out: rate(totals.bytes_out, previous.totals.bytes_out),
+        },
+        taken_at: Instant::now(),
+    };
+
+    *metrics.snapshot.write().unwrap() = snapshot;
+}
+
+fn escape_label(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+pub fn to_prometheus(snapshot: &MetricsSnapshot) -> String {
+    let mut out = String::new();
+
+    let mut metric = |name: &str, kind: &str, help: &str, value: String| {
+        let _ = writeln!(out, "# HELP rosa_{name} {help}\n# TYPE rosa_{name} {kind}\nrosa_{name} {value}");
+    };
+
+    metric("players", "gauge", "Players online.", snapshot.players.len().to_string());
+    metric("max_players", "gauge", "Player limit.", snapshot.max_players.to_string());
+    metric("round_number", "gauge", "Current round.", snapshot.round_number.to_string());
+    metric("network_tick", "counter", "Network ticks since the server started.", snapshot.network_tick.to_string());
+    metric("tick_duration_seconds", "gauge", "How long the last tick took.", (snapshot.tick_duration_ms / 1000.0).to_string());
+    metric("global_events", "gauge", "Events in the global event queue.", snapshot.global_events.to_string());
+    metric("packets_in_total", "counter", "Packets received.", snapshot.totals.packets_in.to_string());
+    metric("packets_out_total", "counter", "Packets sent.", snapshot.totals.packets_out.to_string());
+    metric("bytes_in_total", "counter", "Bytes received.", snapshot.totals.bytes_in.to_string());
+    metric("bytes_out_total", "counter", "Bytes sent.", snapshot.totals.bytes_out.to_string());
+    metric("packets_in_per_second", "gauge", "Packets received over the last second.", snapshot.per_second.packets_in.to_string());
+    metric("packets_out_per_second", "gauge", "Packets sent over the last second.", snapshot.per_second.packets_out.to_string());
+
+    let _ = writeln!(out, "# HELP rosa_game_state Current game state.\n# TYPE rosa_game_state gauge");
+    let _ = writeln!(out, "rosa_game_state{{state=\"{:?}\",gamemode=\"{:?}\"}} 1", snapshot.game_state, snapshot.gamemode);
+
+    for (name, help, outgoing) in [
+        ("client_bytes_in_total", "Bytes received from each player.", false),
+        ("client_bytes_out_total", "Bytes sent to each player.", true),
+    ] {
+        let _ = writeln!(out, "# HELP rosa_{name} {help}\n# TYPE rosa_{name} counter");
+
+        for player in &snapshot.players {
+            let _ = writeln!(
+                out,
+                "rosa_{name}{{client_id=\"{}\",account_id=\"{}\",name=\"{}\"}} {}",
+                player.client_id,
+                player.account_id,
+                escape_label(&player.name),
+                if outgoing { player.traffic.bytes_out } else { player.traffic.bytes_in }
+            );
+        }
+    }
+
+    out
+}
+
+fn respond(mut stream: TcpStream, metrics: &Metrics) {
+    let _ = stream.set_read_timeout(Some(Duration::from_secs(2)));
+
+    let mut request = [0; 1024];
+    let Ok(size) = stream.read(&mut request) else {
+        return;
+    };
+
+    let request = String::from_utf8_lossy(&request[..size]);
+    let mut parts = request.split_whitespace();
+
+    let (status, content_type, body) = match (parts.next(), parts.next()) {
+        (Some("GET"), Some("/" | "/status")) => {
+            let snapshot = metrics.snapshot.read().unwrap();
+
+            ("200 OK", "application/json", serde_json::to_string_pretty(&*snapshot).unwrap_or_default())
+        }
+        (Some("GET"), Some("/metrics")) => ("200 OK", "text/plain; version=0.0.4", to_prometheus(&metrics.snapshot.read().unwrap())),
+        (Some("GET"), _) => ("404 Not Found", "text/plain", "Try /status or /metrics.\n".to_string()),
+        _ => ("405 Method Not Allowed", "text/plain", "Only GET is supported.\n".to_string()),
+    };
+
+    let _ = write!(
+        stream,
+        "HTTP/1.1 {status}\r\nContent-Type: {content_type}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+        body.len()
+    );
+}
+
+// Serves /status as JSON and /metrics for Prometheus, off while metrics_port is 0.
+pub fn start(config: &ConfigMain, metrics: Arc<Metrics>) {
+    if config.metrics_port == 0 {
+        info!("metrics", "Disabled, set metrics_port to turn it on.");
+
+        return;
+    }
+
+    let address = format!("{}:{}", config.metrics_address, config.metrics_port);
+
+    let listener = match TcpListener::bind(&address) {
+        Ok(listener) => listener,
+        Err(err) => {
+            error!("metrics", "Failed to listen on {address}: {err}");
+
+            return;
+        }
+    };
+
+    info!("metrics", "Listening on http://{address}");
+
+    std::thread::spawn(move || {
+        for stream in listener.incoming().map_while(Result::ok) {
+            respond(stream, &metrics);
+        }
+    });
+}
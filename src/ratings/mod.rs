@@ -0,0 +1,24 @@
+// The original server stores ratings out of 1000, and starts everyone on a perfect corporate rating.
+pub const MAX_RATING: u32 = 1000;
+pub const STARTING_CORP_RATING: u32 = MAX_RATING;
+
+// A good corporate rating takes up to 20% off, a criminal one adds up to 50%.
+pub fn store_price(base_price: i32, corp_rating: u32, crim_rating: u32) -> i32 {
+    let discount = 0.2 * corp_rating.min(MAX_RATING) as f32 / MAX_RATING as f32;
+    let markup = 0.5 * crim_rating.min(MAX_RATING) as f32 / MAX_RATING as f32;
+
+    (base_price as f32 * (1.0 - discount + markup)).round() as i32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn store_price_scales_with_ratings() {
+        assert_eq!(store_price(1000, 0, 0), 1000);
+        assert_eq!(store_price(1000, MAX_RATING, 0), 800);
+        assert_eq!(store_price(1000, 0, MAX_RATING), 1500);
+        assert_eq!(store_price(1000, STARTING_CORP_RATING * 2, 0), 800);
+    }
+}
@@ -0,0 +1,151 @@
+use std::net::SocketAddr;
+
+use tokio::{
+    io::{AsyncBufReadExt, AsyncWriteExt, BufReader},
+    net::{TcpListener, TcpStream},
+    sync::mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender},
+};
+
+use crate::{
+    app_state::AppState,
+    commands::{run_command, CommandContext},
+    config::config_main::ConfigMain,
+    logging,
+};
+
+// Line based remote admin over TCP.
+//
+// The first line has to be rcon_password, after that every line is a command just like on the
+// server console. Every line sent back starts with a tag so tools can tell them apart:
+//   OK / ERR <reason>   answer to the password
+//   OUT <text>          output from a command
+//   END                 the command finished
+//   LOG <text>          a line from the server log, after `log on`
+pub struct RconRequest {
+    pub line: String,
+    pub output: UnboundedSender<String>,
+}
+
+// The main loop picks requests up with `try_recv`, so commands run on the game thread.
+pub fn start(config: &ConfigMain) -> UnboundedReceiver<RconRequest> {
+    let (tx, rx) = unbounded_channel();
+
+    if config.rcon_password.is_empty() || config.rcon_port == 0 {
+        info!("rcon", "Disabled, set rcon_password and rcon_port to turn it on.");
+
+        return rx;
+    }
+
+    let address = format!("{}:{}", config.rcon_address, config.rcon_port);
+    let password = config.rcon_password.clone();
+
+    // The game loop never yields to the main runtime, so RCON gets a thread and runtime of its own.
+    std::thread::spawn(move || {
+        let runtime = tokio::runtime::Builder::new_current_thread().enable_all().build().expect("Failed to start the RCON runtime");
+
+        runtime.block_on(async move {
+            let listener = match TcpListener::bind(&address).await {
+                Ok(listener) => listener,
+                Err(err) => {
+                    error!("rcon", "Failed to listen on {address}: {err}");
+
+                    return;
+                }
+            };
+
+            info!("rcon", "Listening on {address}");
+
+            while let Ok((stream, peer)) = listener.accept().await {
+                tokio::spawn(handle_client(stream, peer, password.clone(), tx.clone()));
+            }
+        });
+    });
+
+    rx
+}
+
+pub fn run_request(request: RconRequest, state: &AppState) {
+    let line = request.line.trim();
+    let line = line.strip_prefix('/').unwrap_or(line);
+
+    run_command(&mut CommandContext { client: None, state, output: Some(request.output) }, line);
+}
+
+async fn next_log_line(log: &mut Option<UnboundedReceiver<String>>) -> Option<String> {
+    match log {
+        Some(log) => log.recv().await,
+        None => std::future::pending().await,
+    }
+}
+
+async fn handle_client(stream: TcpStream, peer: SocketAddr, password: String, requests: UnboundedSender<RconRequest>) {
+    let (reader, mut writer) = stream.into_split();
+    let mut lines = BufReader::new(reader).lines();
+
+    if !matches!(lines.next_line().await, Ok(Some(line)) if line.trim_end() == password) {
+        warn!("rcon", "{peer} sent the wrong password.");
+
+        let _ = writer.write_all(b"ERR Wrong password.\n").await;
+
+        return;
+    }
+
+    info!("rcon", "{peer} logged in.");
+
+    if writer.write_all(b"OK\n").await.is_err() {
+        return;
+    }
+
+    let mut log = None;
+
+    loop {
+        let sent = tokio::select! {
+            line = lines.next_line() => {
+                let Ok(Some(line)) = line else {
+                    break;
+                };
+
+                match line.trim() {
+                    "log on" => {
+                        log = Some(logging::subscribe());
+
+                        writer.write_all(b"OUT Following the server log.\nEND\n").await
+                    }
+                    "log off" => {
+                        log = None;
+
+                        writer.write_all(b"OUT Stopped following the server log.\nEND\n").await
+                    }
+                    line => {
+                        debug!("rcon", "{peer} ran: {line}");
+
+                        let (output, mut replies) = unbounded_channel();
+
+                        if requests.send(RconRequest { line: line.to_string(), output }).is_err() {
+                            break;
+                        }
+
+                        // The sender is dropped once the command has run.
+                        let mut reply = String::new();
+
+                        while let Some(line) = replies.recv().await {
+                            reply.push_str(&format!("OUT {line}\n"));
+                        }
+
+                        reply.push_str("END\n");
+
+                        writer.write_all(reply.as_bytes()).await
+                    }
+                }
+            }
+
+            Some(line) = next_log_line(&mut log) => writer.write_all(format!("LOG {line}\n").as_bytes()).await,
+        };
+
+        if sent.is_err() {
+            break;
+        }
+    }
+
+    info!("rcon", "{peer} disconnected.");
+}
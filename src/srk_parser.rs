@@ -1,111 +1,370 @@
-
-use std::{fs::{File, OpenOptions}, path::Path};
+use std::{
+    collections::BTreeMap,
+    fs::File,
+    io::{BufReader, BufWriter},
+    path::Path,
+    sync::OnceLock,
+};
 
 use binrw::{BinRead, BinWrite};
+use crossbeam::channel::{bounded, unbounded, Sender};
+use serde::{de::DeserializeOwned, Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::{
+    access::{bans::{BanList, BANS_FILE}, permissions::Role},
+    corporations::{Corporation, CORPORATIONS_FILE},
+    packets::masterserver::auth::MasterServerAuthPacket,
+    ratings::STARTING_CORP_RATING,
+    stocks::StockHoldings,
+};
+
+pub const SRK_FILE: &str = "server.srk";
+// Share holdings by account id. The SRK format has no room for them.
+pub const STOCKS_FILE: &str = "stocks.json";
+// Roles by account id, only for accounts that are more than a player.
+pub const ROLES_FILE: &str = "roles.json";
 
-use crate::packets::masterserver::auth::MasterServerAuthPacket;
+// 0 and 1: The original Sub Rosa format.
+// 2: The two unused u32s after steam_id hold the packed character customization.
+pub const SRK_VERSION: u32 = 2;
 
-#[derive(BinRead, BinWrite, Clone)]
+// Saves are handed to a single writer thread so the game loop never touches the disk.
+// The optional sender is told once that snapshot is on disk.
+static SRK_WRITER: OnceLock<Sender<(SrkData, Option<Sender<()>>)>> = OnceLock::new();
+
+#[derive(BinRead, BinWrite, Clone, Serialize, Deserialize)]
 #[brw(little)]
 pub struct SrkData {
     pub version: u32,
     pub server_id: u32,
     pub player_count: u32,
     #[br(count = player_count)]
-    pub players: Vec<SrkPlayerData>
+    pub players: Vec<SrkPlayerData>,
+    // Not in the SRK either, read from and saved to BANS_FILE. The accounts only say when a ban ends.
+    #[brw(ignore)]
+    #[serde(skip)]
+    pub bans: BanList,
+    // Same for corporations, read from and saved to CORPORATIONS_FILE.
+    #[brw(ignore)]
+    #[serde(skip)]
+    pub corporations: Vec<Corporation>,
 }
 
-#[derive(BinRead, BinWrite, Clone)]
+#[derive(BinRead, BinWrite, Clone, Serialize, Deserialize)]
 #[brw(little)]
 pub struct SrkPlayerData {
     pub account_id: u32,
     pub phone_number: u32,
     pub steam_id: u64,
-    pub unused_0: u32,
-    pub unused_1: u32,
+    // Unused in the original format, see CharacterCustomization::to_saved.
+    pub appearance_0: u32,
+    pub appearance_1: u32,
+    #[serde(serialize_with = "serialize_name", deserialize_with = "deserialize_name")]
     pub player_name: [u8; 32],
-    pub money: u32,
+    // Signed, the original server lets it go negative.
+    pub money: i32,
     pub corp_rating: u32,
     pub crim_rating: u32,
     pub spawn_timer: u32,
     pub play_time: u32,
+    // The original server sets these for some accounts and we don't know what they mean, so they're kept as they are.
     pub unused_2: u32,
     pub unused_3: u32,
     pub ban_time: u32,
+    // Not in the SRK itself, read from and saved to STOCKS_FILE.
+    #[brw(ignore)]
+    #[serde(default)]
+    pub stocks: StockHoldings,
+    // Same for roles, read from and saved to ROLES_FILE.
+    #[brw(ignore)]
+    #[serde(default)]
+    pub role: Role,
+}
+
+// Names are dumped as text so they can be edited by hand.
+fn serialize_name<S: Serializer>(name: &[u8; 32], serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&name_from_bytes(name))
+}
+
+fn deserialize_name<'de, D: Deserializer<'de>>(deserializer: D) -> Result<[u8; 32], D::Error> {
+    let mut name = String::deserialize(deserializer)?.into_bytes();
+    name.resize(32, 0);
+
+    Ok(name.try_into().unwrap())
+}
+
+// The original server leaves junk after the terminator, so stop at the first one.
+fn name_from_bytes(name: &[u8; 32]) -> String {
+    let len = name.iter().position(|byte| *byte == 0).unwrap_or(name.len());
+
+    String::from_utf8_lossy(&name[..len]).to_string()
+}
+
+impl SrkPlayerData {
+    pub fn new(auth: &MasterServerAuthPacket) -> Self {
+        let mut name = auth.name.clone().into_bytes();
+        name.resize(32, 0);
+
+        SrkPlayerData {
+            account_id: auth.account_id,
+            ban_time: 0,
+            phone_number: auth.phone_number,
+            steam_id: auth.steam_id,
+            corp_rating: STARTING_CORP_RATING,
+            crim_rating: 0,
+            money: 0,
+            play_time: 0,
+            player_name: name.try_into().unwrap(),
+            spawn_timer: 0,
+            appearance_0: 0,
+            appearance_1: 0,
+            unused_2: 0,
+            unused_3: 0,
+            stocks: StockHoldings::default(),
+            role: Role::Player,
+        }
+    }
+
+    pub fn name(&self) -> String {
+        name_from_bytes(&self.player_name)
+    }
 }
 
 impl SrkData {
     pub fn read_from_file() -> Self {
-        println!("[SRK] Attempting to read server.srk...");
+        info!("srk", "Attempting to read server.srk...");
 
-        let file_name = "server.srk";
-
-        if !Path::new(file_name).exists() {
+        if !Path::new(SRK_FILE).exists() {
             let data = SrkData {
                 player_count: 0,
                 players: vec![],
                 server_id: 800815,
-                version: 1
+                version: SRK_VERSION,
+                bans: BanList::default(),
+                corporations: vec![],
             };
 
-            let mut file = File::create(file_name).unwrap();
-            let _ = data.write(&mut file);
+            data.write_to_path(SRK_FILE).expect("Failed to create server.srk");
         }
 
-        let mut file = std::fs::File::open(file_name).expect("Failed to open server.srk");
-        let data = SrkData::read(&mut file).unwrap();
+        let data = SrkData::read_from_path(SRK_FILE)
+            .and_then(|data| data.read_stocks(STOCKS_FILE))
+            .and_then(|data| data.read_roles(ROLES_FILE))
+            .and_then(SrkData::read_side_files)
+            .unwrap_or_else(|err| panic!("Failed to read server.srk: {err}"));
 
-        println!("[SRK] Loaded SRK successfully, found {} players.", data.player_count);
+        info!("srk", "Loaded SRK successfully, found {} players.", data.player_count);
 
         data
     }
 
-    pub fn create_account(&mut self, data: &MasterServerAuthPacket) {
-        let existing_account = self.players.clone().into_iter().filter(|acc| acc.account_id == data.account_id).collect::<Vec<SrkPlayerData>>();
+    pub fn read_from_path(path: &str) -> Result<Self, String> {
+        let mut file = BufReader::new(File::open(path).map_err(|err| err.to_string())?);
+        let data = SrkData::read(&mut file).map_err(|err| err.to_string())?;
 
-        let mut name = data.name.clone().into_bytes();
-        name.resize(32, 0);
+        data.migrate()
+    }
 
-        if existing_account.is_empty() {
-            let account = SrkPlayerData {
-                account_id: data.account_id,
-                ban_time: 0,
-                phone_number: data.phone_number,
-                steam_id: data.steam_id,
-                corp_rating: 0,
-                crim_rating: 0,
-                money: 0,
-                play_time: 0,
-                player_name: name.try_into().unwrap(),
-                spawn_timer: 0,
-                unused_0: 0,
-                unused_1: 0,
-                unused_2: 0,
-                unused_3: 0
-            };
+    pub fn read_stocks(mut self, path: &str) -> Result<Self, String> {
+        let holdings: BTreeMap<u32, StockHoldings> = read_json(path)?;
+
+        for player in &mut self.players {
+            player.stocks = holdings.get(&player.account_id).copied().unwrap_or_default();
+        }
+
+        Ok(self)
+    }
+
+    // Only accounts that hold something, so the file stays small.
+    pub fn write_stocks(&self, path: &str) -> Result<(), String> {
+        let holdings = self
+            .players
+            .iter()
+            .filter(|player| player.stocks.iter().any(|shares| *shares > 0))
+            .map(|player| (player.account_id, player.stocks))
+            .collect::<BTreeMap<u32, StockHoldings>>();
+
+        write_json(path, &holdings)
+    }
+
+    pub fn read_roles(mut self, path: &str) -> Result<Self, String> {
+        let roles: BTreeMap<u32, Role> = read_json(path)?;
+
+        for player in &mut self.players {
+            player.role = roles.get(&player.account_id).copied().unwrap_or_default();
+        }
+
+        Ok(self)
+    }
+
+    pub fn write_roles(&self, path: &str) -> Result<(), String> {
+        let roles = self
+            .players
+            .iter()
+            .filter(|player| player.role > Role::Player)
+            .map(|player| (player.account_id, player.role))
+            .collect::<BTreeMap<u32, Role>>();
+
+        write_json(path, &roles)
+    }
+
+    // Everything kept beside the SRK that isn't per account.
+    pub fn read_side_files(mut self) -> Result<Self, String> {
+        self.bans = read_json(BANS_FILE)?;
+        self.corporations = read_json(CORPORATIONS_FILE)?;
+
+        Ok(self)
+    }
+
+    // The SRK and the files kept beside it.
+    pub fn write_files(&self) -> Result<(), String> {
+        self.write_to_path(SRK_FILE)?;
+        self.write_stocks(STOCKS_FILE)?;
+        self.write_roles(ROLES_FILE)?;
+        write_json(BANS_FILE, &self.bans)?;
+        write_json(CORPORATIONS_FILE, &self.corporations)
+    }
 
-            self.player_count += 1;
-            self.players.push(account);
+    // Brings older files up to SRK_VERSION, they get written back in the new format on the next save.
+    pub fn migrate(mut self) -> Result<Self, String> {
+        if self.version > SRK_VERSION {
+            return Err(format!("version {} is newer than this server understands ({SRK_VERSION})", self.version));
+        }
+
+        if self.version < 2 {
+            for player in &mut self.players {
+                player.appearance_0 = 0;
+                player.appearance_1 = 0;
+            }
+        }
+
+        if self.version != SRK_VERSION {
+            info!("srk", "Migrated SRK from version {} to {SRK_VERSION}.", self.version);
 
-            self.save();
+            self.version = SRK_VERSION;
         }
+
+        Ok(self)
+    }
+
+    // Writes next to the real file and renames over it, so a crash mid-write can't corrupt it.
+    pub fn write_to_path(&self, path: &str) -> Result<(), String> {
+        let temp_path = format!("{path}.tmp");
+
+        let mut file = BufWriter::new(File::create(&temp_path).map_err(|err| err.to_string())?);
+        self.write(&mut file).map_err(|err| err.to_string())?;
+
+        file.into_inner().map_err(|err| err.to_string())?.sync_all().map_err(|err| err.to_string())?;
+
+        std::fs::rename(&temp_path, path).map_err(|err| err.to_string())
+    }
+
+    pub fn get_account_mut(&mut self, account_id: u32) -> Option<&mut SrkPlayerData> {
+        self.players.iter_mut().find(|acc| acc.account_id == account_id)
     }
 
     pub fn save(&self) {
-        let file = OpenOptions::new().write(true).create(true).truncate(true).open("server.srk");
+        self.send_to_writer(None);
+    }
+
+    // Blocks until the save is on disk, for when the server is about to exit.
+    pub fn save_and_wait(&self) {
+        let (tx, rx) = bounded(1);
+
+        self.send_to_writer(Some(tx));
+
+        let _ = rx.recv();
+    }
+
+    fn send_to_writer(&self, done: Option<Sender<()>>) {
+        let writer = SRK_WRITER.get_or_init(|| {
+            let (tx, rx) = unbounded::<(SrkData, Option<Sender<()>>)>();
+
+            std::thread::spawn(move || {
+                while let Ok((mut data, done)) = rx.recv() {
+                    let mut waiting = vec![done];
+
+                    // Only the newest snapshot matters if a few queued up.
+                    while let Ok((newer, done)) = rx.try_recv() {
+                        data = newer;
+                        waiting.push(done);
+                    }
+
+                    match data.write_files() {
+                        Ok(_) => info!("srk", "Successfully saved SRK data!"),
+                        Err(err) => error!("srk", "Failed to save SRK data! {err}"),
+                    }
 
-        if let Ok(mut file) = file {
-            let res = self.write(&mut file);
+                    for done in waiting.into_iter().flatten() {
+                        let _ = done.send(());
+                    }
+                }
+            });
 
-            if res.is_err() {
-                println!("[SRK] Failed to save SRK data!");
-            } else {
-                println!("[SRK] Successfully saved SRK data!")
+            tx
+        });
+
+        let _ = writer.send((self.clone(), done));
+    }
+}
+
+// For the files kept beside the SRK, one that doesn't exist yet reads as empty.
+pub fn read_json<T: DeserializeOwned + Default>(path: &str) -> Result<T, String> {
+    if !Path::new(path).exists() {
+        return Ok(T::default());
+    }
+
+    let file = BufReader::new(File::open(path).map_err(|err| err.to_string())?);
+
+    serde_json::from_reader(file).map_err(|err| format!("{path}: {err}"))
+}
+
+// Same temp file and rename as the SRK itself.
+pub fn write_json<T: Serialize>(path: &str, value: &T) -> Result<(), String> {
+    let temp_path = format!("{path}.tmp");
+
+    let mut file = BufWriter::new(File::create(&temp_path).map_err(|err| err.to_string())?);
+    serde_json::to_writer_pretty(&mut file, value).map_err(|err| err.to_string())?;
+
+    file.into_inner().map_err(|err| err.to_string())?.sync_all().map_err(|err| err.to_string())?;
+
+    std::fs::rename(&temp_path, path).map_err(|err| err.to_string())
+}
+
+// `rosa2 srk dump [file.json]` and `rosa2 srk import <file.json>`
+pub fn run_cli(args: &[String]) {
+    match (args.first().map(String::as_str), args.get(1)) {
+        (Some("dump"), path) => {
+            let data = SrkData::read_from_path(SRK_FILE)
+                .and_then(|data| data.read_stocks(STOCKS_FILE))
+                .and_then(|data| data.read_roles(ROLES_FILE))
+                .unwrap_or_else(|err| panic!("Failed to read server.srk: {err}"));
+
+            match path {
+                Some(path) => {
+                    let file = File::create(path).expect("Failed to create output file");
+                    serde_json::to_writer_pretty(BufWriter::new(file), &data).expect("Failed to write JSON");
+
+                    println!("[SRK] Dumped {} players to {path}.", data.players.len());
+                }
+                None => println!("{}", serde_json::to_string_pretty(&data).expect("Failed to write JSON")),
             }
+        }
 
-            return;
+        (Some("import"), Some(path)) => {
+            let file = File::open(path).expect("Failed to open input file");
+            let mut data: SrkData = serde_json::from_reader(BufReader::new(file)).unwrap_or_else(|err| panic!("Failed to parse {path}: {err}"));
+
+            data.player_count = data.players.len() as u32;
+
+            // Bans and corporations aren't in the dump, keep whatever is already there.
+            let data = data.migrate().and_then(SrkData::read_side_files).unwrap_or_else(|err| panic!("Failed to import {path}: {err}"));
+
+            data.write_files().unwrap_or_else(|err| panic!("Failed to write server.srk: {err}"));
+
+            println!("[SRK] Imported {} players from {path}.", data.players.len());
         }
 
-        println!("[SRk] Failed to open SRK file!");
+        _ => println!("Usage: srk dump [file.json] | srk import <file.json>"),
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,113 @@
+use dashmap::DashMap;
+
+use crate::packets::Team;
+
+pub const CORPORATIONS: [Team; 5] = [Team::Goldmen, Team::Monsota, Team::OXS, Team::Nexaco, Team::Pentacom];
+
+pub const STARTING_SHARE_PRICE: i32 = 500;
+pub const MIN_SHARE_PRICE: i32 = 10;
+pub const MAX_SHARE_PRICE: i32 = 100_000;
+pub const MAX_SHARES_PER_CORPORATION: u8 = 200;
+// Every $10 a corporation's players earn over a round is a point of performance.
+pub const EARNINGS_PER_POINT: i64 = 10;
+// Every share bought over a round is this many points, every share sold takes them away.
+pub const POINTS_PER_SHARE_TRADED: i32 = 5;
+
+// Shares held per corporation, indexed by team id.
+pub type StockHoldings = [u8; 8];
+
+#[derive(Default)]
+pub struct StockManager {
+    pub stocks: DashMap<Team, Stock>,
+    // Account id to their team and net worth when the round started.
+    round_start: DashMap<u32, (Team, i64)>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Stock {
+    pub team: Team,
+    pub price: i32,
+    pub performance: i32,
+}
+
+impl StockManager {
+    pub fn new() -> Self {
+        let stocks = DashMap::new();
+
+        for team in CORPORATIONS {
+            stocks.insert(
+                team,
+                Stock {
+                    team,
+                    price: STARTING_SHARE_PRICE,
+                    performance: 0,
+                },
+            );
+        }
+
+        Self {
+            stocks,
+            round_start: DashMap::new(),
+        }
+    }
+
+    pub fn price(&self, team: Team) -> Option<i32> {
+        self.stocks.get(&team).map(|stock| stock.price)
+    }
+
+    // Performance is what a corporation's players earned over a round plus the demand for its shares, applied to the
+    // share price once it ends.
+    fn add_performance(&self, team: Team, amount: i32) {
+        if let Some(mut stock) = self.stocks.get_mut(&team) {
+            stock.performance = stock.performance.saturating_add(amount);
+        }
+    }
+
+    // Negative for shares sold.
+    pub fn trade(&self, team: Team, shares: i32) {
+        self.add_performance(team, shares.saturating_mul(POINTS_PER_SHARE_TRADED));
+    }
+
+    // (account id, team, net worth) for everyone playing.
+    pub fn start_round(&self, players: &[(u32, Team, i64)]) {
+        self.round_start.clear();
+
+        for (account_id, team, worth) in players {
+            self.round_start.insert(*account_id, (*team, *worth));
+        }
+    }
+
+    // Prices only change here. Trades happen at the current price, so they leave net worth alone and only count through
+    // trade. Anyone who joined late or switched teams is left out of the earnings.
+    pub fn end_round(&self, players: &[(u32, Team, i64)]) {
+        for (account_id, team, worth) in players {
+            if let Some((_, (start_team, start_worth))) = self.round_start.remove(account_id)
+                && start_team == *team
+            {
+                let points = (worth - start_worth) / EARNINGS_PER_POINT;
+
+                self.add_performance(*team, points.clamp(i32::MIN as i64, i32::MAX as i64) as i32);
+            }
+        }
+
+        self.round_start.clear();
+
+        for mut stock in self.stocks.iter_mut() {
+            // Every point of performance moves the price by 0.1%, capped at +-50% a round, with a bit of noise on top.
+            let performance = (stock.performance as f32 / 1000.0).clamp(-0.5, 0.5);
+            let noise = rand::random_range(-0.05..=0.05);
+
+            let price = stock.price as f32 * (1.0 + performance + noise);
+
+            stock.price = (price as i32).clamp(MIN_SHARE_PRICE, MAX_SHARE_PRICE);
+            stock.performance = 0;
+        }
+    }
+
+    pub fn portfolio_value(&self, holdings: &StockHoldings) -> i32 {
+        CORPORATIONS
+            .iter()
+            .map(|team| holdings[*team as usize] as i32 * self.price(*team).unwrap_or(0))
+            .sum()
+    }
+}
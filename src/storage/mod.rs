@@ -0,0 +1,103 @@
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    access::bans::{BanList, BANS_FILE},
+    config::config_main::ConfigMain,
+    corporations::{Corporation, CORPORATIONS_FILE},
+    packets::{masterserver::auth::MasterServerAuthPacket, GameMode},
+    srk_parser::{read_json, SrkData, SrkPlayerData, SRK_FILE},
+    storage::sqlite::SqliteStore,
+};
+
+pub mod srk;
+pub mod sqlite;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PlayerStoreType {
+    Srk,
+    Sqlite,
+}
+
+// Where accounts live. `SrkPlayerData` doubles as the account record for every backend.
+pub trait PlayerStore {
+    fn get_account(&self, account_id: u32) -> Option<SrkPlayerData>;
+    fn create_account(&mut self, auth: &MasterServerAuthPacket);
+    fn update_account(&mut self, account: &SrkPlayerData);
+    fn find_account(&self, query: &str) -> Option<u32>;
+    fn all_accounts(&self) -> Vec<SrkPlayerData>;
+
+    fn add_stat(&mut self, account_id: u32, mode: GameMode, stat: &str, amount: i64);
+    fn get_stats(&self, account_id: u32, mode: GameMode) -> Vec<(String, i64)>;
+
+    // Ban reasons and IP range bans. When an account ban ends is on the account itself.
+    fn load_bans(&self) -> BanList;
+    fn save_bans(&mut self, bans: &BanList);
+
+    fn load_corporations(&self) -> Vec<Corporation>;
+    fn save_corporations(&mut self, corporations: &[Corporation]);
+
+    // Groups the updates between them into one write, for backends where every write goes to disk.
+    fn begin_batch(&mut self) {}
+    fn end_batch(&mut self) {}
+
+    // Pushes any pending changes out to disk.
+    fn save(&self);
+
+    // Saves and waits for it to finish, before the server exits.
+    fn close(&self) {
+        self.save();
+    }
+
+    fn account_name(&self, account_id: u32) -> String {
+        self.get_account(account_id).map_or(account_id.to_string(), |acc| acc.name())
+    }
+}
+
+pub fn open_player_store(config: &ConfigMain) -> Box<dyn PlayerStore + Send> {
+    match config.player_store {
+        PlayerStoreType::Srk => Box::new(SrkData::read_from_file()),
+        PlayerStoreType::Sqlite => {
+            let mut store = SqliteStore::open(&config.sqlite_path);
+
+            // Bring over everyone from the SRK the first time we switch.
+            if store.all_accounts().is_empty() && Path::new(SRK_FILE).exists() {
+                let data = SrkData::read_from_file();
+
+                store.begin_batch();
+
+                for account in &data.players {
+                    store.update_account(account);
+                }
+
+                store.end_batch();
+
+                info!("sqlite", "Imported {} players from {SRK_FILE}.", data.players.len());
+            }
+
+            if store.load_bans().is_empty() {
+                let bans: BanList = read_json(BANS_FILE).unwrap_or_else(|err| panic!("Failed to read {BANS_FILE}: {err}"));
+
+                if !bans.is_empty() {
+                    store.save_bans(&bans);
+
+                    info!("sqlite", "Imported {} ban reasons and {} IP range bans from {BANS_FILE}.", bans.reasons.len(), bans.ranges.len());
+                }
+            }
+
+            if store.load_corporations().is_empty() {
+                let corporations: Vec<Corporation> =
+                    read_json(CORPORATIONS_FILE).unwrap_or_else(|err| panic!("Failed to read {CORPORATIONS_FILE}: {err}"));
+
+                if !corporations.is_empty() {
+                    store.save_corporations(&corporations);
+
+                    info!("sqlite", "Imported {} corporations from {CORPORATIONS_FILE}.", corporations.len());
+                }
+            }
+
+            Box::new(store)
+        }
+    }
+}
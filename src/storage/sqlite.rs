@@ -0,0 +1,332 @@
+use std::net::Ipv4Addr;
+
+use rusqlite::{params, Connection, OptionalExtension, Row};
+
+use crate::{
+    access::bans::{BanList, IpRangeBan},
+    corporations::Corporation,
+    packets::{masterserver::auth::MasterServerAuthPacket, GameMode},
+    srk_parser::SrkPlayerData,
+    stocks::{StockHoldings, CORPORATIONS},
+    storage::PlayerStore,
+};
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS accounts (
+    account_id INTEGER PRIMARY KEY,
+    phone_number INTEGER NOT NULL,
+    steam_id INTEGER NOT NULL,
+    name TEXT NOT NULL,
+    money INTEGER NOT NULL,
+    corp_rating INTEGER NOT NULL,
+    crim_rating INTEGER NOT NULL,
+    spawn_timer INTEGER NOT NULL,
+    play_time INTEGER NOT NULL,
+    stocks BLOB NOT NULL,
+    ban_time INTEGER NOT NULL,
+    appearance_0 INTEGER NOT NULL,
+    appearance_1 INTEGER NOT NULL,
+    role TEXT NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS accounts_name ON accounts (name COLLATE NOCASE);
+
+CREATE TABLE IF NOT EXISTS stats (
+    account_id INTEGER NOT NULL,
+    mode INTEGER NOT NULL,
+    stat TEXT NOT NULL,
+    value INTEGER NOT NULL,
+    PRIMARY KEY (account_id, mode, stat)
+);
+
+CREATE TABLE IF NOT EXISTS ban_reasons (
+    account_id INTEGER PRIMARY KEY,
+    reason TEXT NOT NULL
+);
+
+-- The network is the address as a number.
+CREATE TABLE IF NOT EXISTS ip_bans (
+    network INTEGER NOT NULL,
+    prefix INTEGER NOT NULL,
+    expires INTEGER NOT NULL,
+    reason TEXT NOT NULL,
+    PRIMARY KEY (network, prefix)
+);
+
+-- Rows are read back in the order they went in, the first employee takes over when a manager quits.
+CREATE TABLE IF NOT EXISTS corporations (
+    team INTEGER PRIMARY KEY,
+    manager INTEGER,
+    budget INTEGER NOT NULL
+);
+
+-- Hired is 0 for people who have only applied.
+CREATE TABLE IF NOT EXISTS corporation_members (
+    account_id INTEGER PRIMARY KEY,
+    team INTEGER NOT NULL,
+    hired INTEGER NOT NULL
+);
+";
+
+const ACCOUNT_COLUMNS: &str =
+    "account_id, phone_number, steam_id, name, money, corp_rating, crim_rating, spawn_timer, play_time, stocks, ban_time, appearance_0, appearance_1, role";
+
+pub struct SqliteStore {
+    connection: Connection,
+}
+
+impl SqliteStore {
+    pub fn open(path: &str) -> Self {
+        info!("sqlite", "Opening {path}...");
+
+        let connection = Connection::open(path).unwrap_or_else(|err| panic!("Failed to open {path}: {err}"));
+
+        // NORMAL only syncs at checkpoints under WAL, a crash can lose the last few commits but never corrupts the database.
+        connection.execute_batch("PRAGMA journal_mode = WAL; PRAGMA synchronous = NORMAL;").expect("Failed to enable WAL");
+        connection.execute_batch(SCHEMA).expect("Failed to create the player tables");
+
+        Self { connection }
+    }
+
+    fn account_from_row(row: &Row) -> rusqlite::Result<SrkPlayerData> {
+        let mut name = row.get::<_, String>(3)?.into_bytes();
+        name.resize(32, 0);
+
+        let mut stocks = StockHoldings::default();
+        let stored = row.get::<_, Vec<u8>>(9)?;
+        let len = stored.len().min(stocks.len());
+        stocks[..len].copy_from_slice(&stored[..len]);
+
+        Ok(SrkPlayerData {
+            account_id: row.get(0)?,
+            phone_number: row.get(1)?,
+            steam_id: row.get::<_, i64>(2)? as u64,
+            player_name: name.try_into().unwrap(),
+            money: row.get(4)?,
+            corp_rating: row.get(5)?,
+            crim_rating: row.get(6)?,
+            spawn_timer: row.get(7)?,
+            play_time: row.get(8)?,
+            stocks,
+            ban_time: row.get(10)?,
+            // Only the SRK has these, nothing reads them.
+            unused_2: 0,
+            unused_3: 0,
+            appearance_0: row.get(11)?,
+            appearance_1: row.get(12)?,
+            role: row.get::<_, String>(13)?.parse().unwrap_or_default(),
+        })
+    }
+
+    fn log_error<T>(result: rusqlite::Result<T>) -> Option<T> {
+        result.inspect_err(|err| error!("sqlite", "Query failed: {err}")).ok()
+    }
+}
+
+impl PlayerStore for SqliteStore {
+    fn get_account(&self, account_id: u32) -> Option<SrkPlayerData> {
+        let query = format!("SELECT {ACCOUNT_COLUMNS} FROM accounts WHERE account_id = ?1");
+
+        Self::log_error(self.connection.query_row(&query, params![account_id], Self::account_from_row).optional()).flatten()
+    }
+
+    fn create_account(&mut self, auth: &MasterServerAuthPacket) {
+        if self.get_account(auth.account_id).is_none() {
+            self.update_account(&SrkPlayerData::new(auth));
+        }
+    }
+
+    fn update_account(&mut self, account: &SrkPlayerData) {
+        let query = format!("INSERT OR REPLACE INTO accounts ({ACCOUNT_COLUMNS}) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14)");
+
+        Self::log_error(self.connection.execute(
+            &query,
+            params![
+                account.account_id,
+                account.phone_number,
+                account.steam_id as i64,
+                account.name(),
+                account.money,
+                account.corp_rating,
+                account.crim_rating,
+                account.spawn_timer,
+                account.play_time,
+                account.stocks.to_vec(),
+                account.ban_time,
+                account.appearance_0,
+                account.appearance_1,
+                account.role.to_string(),
+            ],
+        ));
+    }
+
+    fn find_account(&self, query: &str) -> Option<u32> {
+        if let Ok(account_id) = query.parse::<u32>()
+            && self.get_account(account_id).is_some()
+        {
+            return Some(account_id);
+        }
+
+        Self::log_error(
+            self.connection
+                .query_row("SELECT account_id FROM accounts WHERE name = ?1 COLLATE NOCASE", params![query], |row| row.get(0))
+                .optional(),
+        )
+        .flatten()
+    }
+
+    fn all_accounts(&self) -> Vec<SrkPlayerData> {
+        let query = format!("SELECT {ACCOUNT_COLUMNS} FROM accounts");
+
+        let accounts: rusqlite::Result<Vec<SrkPlayerData>> = try {
+            let mut statement = self.connection.prepare(&query)?;
+
+            statement.query_map([], Self::account_from_row)?.collect::<rusqlite::Result<Vec<SrkPlayerData>>>()?
+        };
+
+        Self::log_error(accounts).unwrap_or_default()
+    }
+
+    fn add_stat(&mut self, account_id: u32, mode: GameMode, stat: &str, amount: i64) {
+        Self::log_error(self.connection.execute(
+            "INSERT INTO stats (account_id, mode, stat, value) VALUES (?1, ?2, ?3, ?4)
+             ON CONFLICT (account_id, mode, stat) DO UPDATE SET value = value + excluded.value",
+            params![account_id, mode as u8, stat, amount],
+        ));
+    }
+
+    fn get_stats(&self, account_id: u32, mode: GameMode) -> Vec<(String, i64)> {
+        let stats: rusqlite::Result<Vec<(String, i64)>> = try {
+            let mut statement = self.connection.prepare("SELECT stat, value FROM stats WHERE account_id = ?1 AND mode = ?2 ORDER BY stat")?;
+
+            statement.query_map(params![account_id, mode as u8], |row| Ok((row.get(0)?, row.get(1)?)))?.collect::<rusqlite::Result<Vec<(String, i64)>>>()?
+        };
+
+        Self::log_error(stats).unwrap_or_default()
+    }
+
+    fn load_bans(&self) -> BanList {
+        let bans: rusqlite::Result<BanList> = try {
+            let mut reasons = self.connection.prepare("SELECT account_id, reason FROM ban_reasons")?;
+            let mut ranges = self.connection.prepare("SELECT network, prefix, expires, reason FROM ip_bans")?;
+
+            BanList {
+                reasons: reasons.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?.collect::<rusqlite::Result<_>>()?,
+                ranges: ranges
+                    .query_map([], |row| {
+                        Ok(IpRangeBan {
+                            network: Ipv4Addr::from(row.get::<_, u32>(0)?),
+                            prefix: row.get(1)?,
+                            expires: row.get(2)?,
+                            reason: row.get(3)?,
+                        })
+                    })?
+                    .collect::<rusqlite::Result<_>>()?,
+            }
+        };
+
+        Self::log_error(bans).unwrap_or_default()
+    }
+
+    // The whole list is small, so it's replaced rather than diffed. A savepoint so it works inside a batch too.
+    fn save_bans(&mut self, bans: &BanList) {
+        let saved: rusqlite::Result<()> = try {
+            self.connection.execute_batch("SAVEPOINT save_bans; DELETE FROM ban_reasons; DELETE FROM ip_bans;")?;
+
+            for (account_id, reason) in &bans.reasons {
+                self.connection.execute("INSERT INTO ban_reasons (account_id, reason) VALUES (?1, ?2)", params![account_id, reason])?;
+            }
+
+            for ban in &bans.ranges {
+                self.connection.execute(
+                    "INSERT INTO ip_bans (network, prefix, expires, reason) VALUES (?1, ?2, ?3, ?4)",
+                    params![u32::from(ban.network), ban.prefix, ban.expires, ban.reason],
+                )?;
+            }
+
+            self.connection.execute_batch("RELEASE save_bans")?
+        };
+
+        if Self::log_error(saved).is_none() {
+            Self::log_error(self.connection.execute_batch("ROLLBACK TO save_bans; RELEASE save_bans"));
+        }
+    }
+
+    fn load_corporations(&self) -> Vec<Corporation> {
+        let corporations: rusqlite::Result<Vec<Corporation>> = try {
+            let mut statement = self.connection.prepare("SELECT team, manager, budget FROM corporations ORDER BY rowid")?;
+            let mut corporations = statement
+                .query_map([], |row| Ok((row.get::<_, u8>(0)?, row.get(1)?, row.get(2)?)))?
+                .collect::<rusqlite::Result<Vec<(u8, Option<u32>, i32)>>>()?
+                .into_iter()
+                .filter_map(|(team, manager, budget)| {
+                    Some(Corporation {
+                        team: CORPORATIONS.into_iter().find(|corp| *corp as u8 == team)?,
+                        manager,
+                        employees: vec![],
+                        applications: vec![],
+                        budget,
+                    })
+                })
+                .collect::<Vec<Corporation>>();
+
+            let mut statement = self.connection.prepare("SELECT account_id, team, hired FROM corporation_members ORDER BY rowid")?;
+
+            for member in statement.query_map([], |row| Ok((row.get::<_, u32>(0)?, row.get::<_, u8>(1)?, row.get::<_, bool>(2)?)))? {
+                let (account_id, team, hired) = member?;
+
+                if let Some(corp) = corporations.iter_mut().find(|corp| corp.team as u8 == team) {
+                    if hired {
+                        corp.employees.push(account_id);
+                    } else {
+                        corp.applications.push(account_id);
+                    }
+                }
+            }
+
+            corporations
+        };
+
+        Self::log_error(corporations).unwrap_or_default()
+    }
+
+    // Replaced as a whole like the bans.
+    fn save_corporations(&mut self, corporations: &[Corporation]) {
+        let saved: rusqlite::Result<()> = try {
+            self.connection.execute_batch("SAVEPOINT save_corporations; DELETE FROM corporations; DELETE FROM corporation_members;")?;
+
+            for corp in corporations {
+                self.connection.execute(
+                    "INSERT INTO corporations (team, manager, budget) VALUES (?1, ?2, ?3)",
+                    params![corp.team as u8, corp.manager, corp.budget],
+                )?;
+
+                let members = corp.employees.iter().map(|id| (id, true)).chain(corp.applications.iter().map(|id| (id, false)));
+
+                for (account_id, hired) in members {
+                    self.connection.execute(
+                        "INSERT OR REPLACE INTO corporation_members (account_id, team, hired) VALUES (?1, ?2, ?3)",
+                        params![account_id, corp.team as u8, hired],
+                    )?;
+                }
+            }
+
+            self.connection.execute_batch("RELEASE save_corporations")?
+        };
+
+        if Self::log_error(saved).is_none() {
+            Self::log_error(self.connection.execute_batch("ROLLBACK TO save_corporations; RELEASE save_corporations"));
+        }
+    }
+
+    fn begin_batch(&mut self) {
+        Self::log_error(self.connection.execute_batch("BEGIN"));
+    }
+
+    fn end_batch(&mut self) {
+        Self::log_error(self.connection.execute_batch("COMMIT"));
+    }
+
+    // Every change is written as it happens.
+    fn save(&self) {}
+}
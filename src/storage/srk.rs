@@ -0,0 +1,76 @@
+use crate::{
+    access::bans::BanList,
+    corporations::Corporation,
+    packets::{masterserver::auth::MasterServerAuthPacket, GameMode},
+    srk_parser::{SrkData, SrkPlayerData},
+    storage::PlayerStore,
+};
+
+impl PlayerStore for SrkData {
+    fn get_account(&self, account_id: u32) -> Option<SrkPlayerData> {
+        self.players.iter().find(|acc| acc.account_id == account_id).cloned()
+    }
+
+    fn create_account(&mut self, auth: &MasterServerAuthPacket) {
+        if self.get_account_mut(auth.account_id).is_some() {
+            return;
+        }
+
+        self.player_count += 1;
+        self.players.push(SrkPlayerData::new(auth));
+    }
+
+    fn update_account(&mut self, account: &SrkPlayerData) {
+        if let Some(existing) = self.get_account_mut(account.account_id) {
+            *existing = account.clone();
+        } else {
+            self.player_count += 1;
+            self.players.push(account.clone());
+        }
+    }
+
+    fn find_account(&self, query: &str) -> Option<u32> {
+        if let Ok(account_id) = query.parse::<u32>()
+            && self.players.iter().any(|acc| acc.account_id == account_id)
+        {
+            return Some(account_id);
+        }
+
+        self.players.iter().find(|acc| acc.name().eq_ignore_ascii_case(query)).map(|acc| acc.account_id)
+    }
+
+    fn all_accounts(&self) -> Vec<SrkPlayerData> {
+        self.players.clone()
+    }
+
+    // The SRK format has nowhere to keep statistics.
+    fn add_stat(&mut self, _account_id: u32, _mode: GameMode, _stat: &str, _amount: i64) {}
+
+    fn get_stats(&self, _account_id: u32, _mode: GameMode) -> Vec<(String, i64)> {
+        vec![]
+    }
+
+    fn load_bans(&self) -> BanList {
+        self.bans.clone()
+    }
+
+    fn save_bans(&mut self, bans: &BanList) {
+        self.bans = bans.clone();
+    }
+
+    fn load_corporations(&self) -> Vec<Corporation> {
+        self.corporations.clone()
+    }
+
+    fn save_corporations(&mut self, corporations: &[Corporation]) {
+        self.corporations = corporations.to_vec();
+    }
+
+    fn save(&self) {
+        SrkData::save(self);
+    }
+
+    fn close(&self) {
+        self.save_and_wait();
+    }
+}
@@ -1,6 +1,6 @@
 use dashmap::DashMap;
 
-use crate::{connection::packets::buf_writer::AlexBufWriter, world::transform::Transform};
+use crate::{connection::{events::event_types::{update_vehicle_type_color::EventUpdateVehicleTypeColor, Event}, packets::buf_writer::AlexBufWriter}, world::transform::Transform};
 
 #[derive(Default)]
 pub struct VehicleManager {
@@ -17,11 +17,22 @@ impl VehicleManager {
 
 pub struct Vehicle {
     pub vehicle_id: u32,
+    pub vehicle_type: u8,
+    pub color: u8,
     pub engine_rpm: u16,
     pub transform: Transform
 }
 
 impl Vehicle {
+    pub fn type_color_event(&self, tick_created: i32) -> Event {
+        Event::UpdateVehicleTypeColor(EventUpdateVehicleTypeColor {
+            tick_created,
+            vehicle_id: self.vehicle_id as i32,
+            vehicle_type: self.vehicle_type,
+            vehicle_color: self.color,
+        })
+    }
+
     pub fn encode_obj(&self, writer: &mut AlexBufWriter) {
         writer.write_bits(self.vehicle_id as i32, 10);
         writer.write_bits(0, 2);
@@ -1,26 +1,102 @@
-use std::sync::RwLock;
+use std::time::{Duration, Instant};
 
-use dashmap::DashMap;
+use dashmap::{DashMap, DashSet};
 
-use crate::connection::packets::serverbound::game::opus::ServerboundGameVoiceFrame;
+use crate::{connection::packets::serverbound::game::opus::ServerboundGameVoiceFrame, world::vector::Vector};
+
+// How many speakers fit in a game packet.
+pub const MAX_SPEAKERS: usize = 8;
+// Past this nobody hears you.
+pub const VOICE_RANGE: f32 = 64.0;
+// Each this much further away drops the volume a level.
+pub const VOICE_FALLOFF: f32 = 16.0;
+// Only 2 bits for a frame's volume.
+const MAX_VOLUME: i32 = 3;
 
 #[derive(Default)]
 pub struct VoiceManager {
-    pub voice_enabled: RwLock<bool>,
-    pub client_voices: DashMap<u32, PlayerVoice>
+    pub client_voices: DashMap<u32, PlayerVoice>,
+    // Account ids, not saved, a restart clears them.
+    pub mutes: DashSet<u32>,
 }
 
 pub struct PlayerVoice {
     pub enabled: bool,
     pub client_id: u32,
-    pub frames: Vec<ServerboundGameVoiceFrame>
+    pub frames: Vec<ServerboundGameVoiceFrame>,
+    // Where they were when they last sent voice.
+    pub position: Vector,
+    pub last_spoke: Option<Instant>,
+}
+
+impl PlayerVoice {
+    // Still talking, or quiet for less than voice_min so the gaps between words don't give their slot away.
+    fn is_speaking(&self, hold: Duration) -> bool {
+        self.enabled || self.last_spoke.is_some_and(|time| time.elapsed() < hold)
+    }
+}
+
+// A speaker as one listener hears them, with the volume already scaled for distance.
+pub struct HeardVoice {
+    pub client_id: u32,
+    pub frames: Vec<ServerboundGameVoiceFrame>,
 }
 
 impl VoiceManager {
     pub fn new() -> Self {
-        VoiceManager {
-            voice_enabled: RwLock::new(true),
-            client_voices: DashMap::new()
-        }
+        Self::default()
+    }
+
+    pub fn mute(&self, account_id: u32) -> bool {
+        self.mutes.insert(account_id)
+    }
+
+    pub fn unmute(&self, account_id: u32) -> bool {
+        self.mutes.remove(&account_id).is_some()
     }
-}
\ No newline at end of file
+
+    pub fn is_muted(&self, account_id: u32) -> bool {
+        self.mutes.contains(&account_id)
+    }
+
+    // The nearest speakers in range of the listener, never themselves. Someone holding their slot after they
+    // went quiet gets empty frames so nothing is played twice.
+    pub fn heard_by(&self, listener: u32, position: Vector, voice_min: u32, voice_boost: u32) -> Vec<HeardVoice> {
+        let hold = Duration::from_millis(voice_min as u64);
+
+        let mut speakers = self
+            .client_voices
+            .iter()
+            .filter(|voice| voice.client_id != listener && voice.frames.len() >= 4 && voice.is_speaking(hold))
+            .map(|voice| ((voice.position - position).magnitude(), voice))
+            .filter(|(distance, _)| *distance <= VOICE_RANGE)
+            .collect::<Vec<_>>();
+
+        speakers.sort_by(|(a, _), (b, _)| a.total_cmp(b));
+
+        speakers
+            .into_iter()
+            .take(MAX_SPEAKERS)
+            .map(|(distance, voice)| {
+                let shift = voice_boost.min(MAX_VOLUME as u32) as i32 - (distance / VOICE_FALLOFF) as i32;
+
+                let frames = voice
+                    .frames
+                    .iter()
+                    .take(4)
+                    .map(|frame| ServerboundGameVoiceFrame {
+                        index: frame.index,
+                        size: if voice.enabled { frame.size } else { 0 },
+                        volume: (frame.volume as i32 + shift).clamp(0, MAX_VOLUME) as u8,
+                        data: if voice.enabled { frame.data.clone() } else { vec![] },
+                    })
+                    .collect();
+
+                HeardVoice {
+                    client_id: voice.client_id,
+                    frames,
+                }
+            })
+            .collect()
+    }
+}